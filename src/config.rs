@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::JsonValue;
+
+/// Flat string settings, sorted by key, as plugins read them.
+pub type Config = BTreeMap<String, String>;
+
+/// Conversion into a `Config`.
+pub trait IntoConfig {
+    fn into_config(self) -> Result<Config, ConfigError>;
+}
+
+/// Conversion back from a `Config`.
+pub trait FromConfig: Sized {
+    fn from_config(config: &Config) -> Self;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// Only an object can become a config.
+    NotAnObject { found: &'static str },
+    /// An array or object as a setting.
+    Nested { key: String, found: &'static str },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::NotAnObject { found } => {
+                write!(f, "expected an object for a config, found {}", found)
+            }
+            ConfigError::Nested { key, found } => {
+                write!(f, "key \"{}\": expected a scalar, found {}", key, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Each scalar is written as by `to_display_string`: strings without their
+/// quotes, anything else as its JSON text.
+impl IntoConfig for &JsonValue {
+    fn into_config(self) -> Result<Config, ConfigError> {
+        let JsonValue::Obj(map) = self else {
+            return Err(ConfigError::NotAnObject {
+                found: self.type_name(),
+            });
+        };
+        map.iter()
+            .map(|(key, value)| match value {
+                JsonValue::Array(_) | JsonValue::Obj(_) | JsonValue::RawJson(_) => {
+                    Err(ConfigError::Nested {
+                        key: key.clone(),
+                        found: value.type_name(),
+                    })
+                }
+                scalar => Ok((key.clone(), scalar.to_display_string())),
+            })
+            .collect()
+    }
+}
+
+/// An object with every setting as a string value.
+impl FromConfig for JsonValue {
+    fn from_config(config: &Config) -> Self {
+        JsonValue::from_pairs(
+            config
+                .iter()
+                .map(|(k, v)| (k.clone(), JsonValue::String(v.clone()))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_into_config() {
+        let value = from_str(
+            r#"{"name": "demo", "port": 8080, "ratio": 0.5, "debug": true, "token": null}"#,
+        )
+        .unwrap();
+        let config = value.into_config().unwrap();
+        let expected: Config = [
+            ("debug", "true"),
+            ("name", "demo"),
+            ("port", "8080"),
+            ("ratio", "0.5"),
+            ("token", "null"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        assert_eq!(config, expected);
+
+        assert_eq!(
+            JsonValue::from_config(&config).get("port"),
+            Some(&JsonValue::String("8080".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_nested_is_an_error() {
+        let value = from_str(r#"{"a": 1, "b": {"c": 2}}"#).unwrap();
+        let error = value.into_config().unwrap_err();
+        assert_eq!(
+            error,
+            ConfigError::Nested {
+                key: "b".to_string(),
+                found: "object"
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "key \"b\": expected a scalar, found object"
+        );
+        assert_eq!(
+            from_str("[1]").unwrap().into_config(),
+            Err(ConfigError::NotAnObject { found: "array" })
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+
+use crate::JsonValue;
+
+/// Conversion from a borrowed `JsonValue` into a Rust type.
+pub trait FromJson: Sized {
+    fn from_json(value: &JsonValue) -> Result<Self, TypeError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+impl TypeError {
+    pub(crate) fn new(expected: &'static str, value: &JsonValue) -> TypeError {
+        TypeError {
+            expected,
+            found: value.type_name(),
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+/// Error returned by `JsonValue::get_as`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GetError {
+    MissingKey(String),
+    WrongType { key: String, error: TypeError },
+}
+
+impl fmt::Display for GetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GetError::MissingKey(key) => write!(f, "missing key \"{}\"", key),
+            GetError::WrongType { key, error } => write!(f, "key \"{}\": {}", key, error),
+        }
+    }
+}
+
+impl std::error::Error for GetError {}
+
+impl JsonValue {
+    /// Looks up `key` and converts its value to `T`.
+    pub fn get_as<T: FromJson>(&self, key: &str) -> Result<T, GetError> {
+        let value = self
+            .get(key)
+            .ok_or_else(|| GetError::MissingKey(key.to_string()))?;
+        T::from_json(value).map_err(|error| GetError::WrongType {
+            key: key.to_string(),
+            error,
+        })
+    }
+}
+
+impl FromJson for JsonValue {
+    fn from_json(value: &JsonValue) -> Result<Self, TypeError> {
+        Ok(value.clone())
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &JsonValue) -> Result<Self, TypeError> {
+        match value {
+            JsonValue::Bool(b) => Ok(*b),
+            _ => Err(TypeError::new("boolean", value)),
+        }
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(value: &JsonValue) -> Result<Self, TypeError> {
+        match value {
+            JsonValue::Number(n) => Ok(*n),
+            JsonValue::Int(i) => Ok(*i as f64),
+            JsonValue::RawNumber(raw) => raw.parse().map_err(|_| TypeError::new("number", value)),
+            _ => Err(TypeError::new("number", value)),
+        }
+    }
+}
+
+impl FromJson for i64 {
+    fn from_json(value: &JsonValue) -> Result<Self, TypeError> {
+        match value {
+            JsonValue::Number(n)
+                if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 =>
+            {
+                Ok(*n as i64)
+            }
+            JsonValue::Int(i) => Ok(*i),
+            _ => Err(TypeError::new("integer", value)),
+        }
+    }
+}
+
+impl FromJson for u64 {
+    fn from_json(value: &JsonValue) -> Result<Self, TypeError> {
+        match value {
+            JsonValue::Number(n) if n.fract() == 0.0 && *n >= 0.0 && *n < u64::MAX as f64 => {
+                Ok(*n as u64)
+            }
+            JsonValue::Int(i) if *i >= 0 => Ok(*i as u64),
+            JsonValue::RawNumber(raw) => raw
+                .parse()
+                .map_err(|_| TypeError::new("unsigned integer", value)),
+            _ => Err(TypeError::new("unsigned integer", value)),
+        }
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &JsonValue) -> Result<Self, TypeError> {
+        match value {
+            JsonValue::String(s) => Ok(s.clone()),
+            _ => Err(TypeError::new("string", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    fn sample() -> JsonValue {
+        Parser::new(r#"{"total": 254, "name": "todos"}"#.to_string())
+            .parse_value()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_get_as() {
+        let value = sample();
+        assert_eq!(value.get_as::<u64>("total"), Ok(254));
+        assert_eq!(value.get_as::<String>("name"), Ok("todos".to_string()));
+    }
+
+    #[test]
+    fn test_get_as_missing_key() {
+        assert_eq!(
+            sample().get_as::<u64>("limit"),
+            Err(GetError::MissingKey("limit".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_get_as_wrong_type() {
+        assert_eq!(
+            sample().get_as::<u64>("name"),
+            Err(GetError::WrongType {
+                key: "name".to_string(),
+                error: TypeError {
+                    expected: "unsigned integer",
+                    found: "string",
+                },
+            })
+        );
+    }
+}
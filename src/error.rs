@@ -0,0 +1,346 @@
+use std::{fmt, io};
+
+use crate::TypeError;
+
+/// Error produced while parsing. Positions are byte offsets into the input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    UnexpectedEof,
+    UnexpectedChar {
+        ch: char,
+        pos: usize,
+    },
+    Expected {
+        expected: char,
+        found: char,
+        pos: usize,
+    },
+    ExpectedKey {
+        pos: usize,
+    },
+    TrailingCharacters {
+        pos: usize,
+    },
+    InvalidUtf8 {
+        offset: usize,
+    },
+    InvalidEscape {
+        pos: usize,
+    },
+    /// Text scanned as a number that `f64` can't read. The tokenizer only
+    /// scans valid numbers, so this means a bug rather than bad input.
+    InvalidNumber {
+        pos: usize,
+    },
+    /// A number written with a `+` sign, see `ParserOptions::number_hints`.
+    LeadingPlus {
+        pos: usize,
+    },
+    /// A number written as `.5`, see `ParserOptions::number_hints`. `fixed`
+    /// is how it should have been written.
+    MissingLeadingZero {
+        fixed: String,
+        pos: usize,
+    },
+    /// A number literal too large for an `f64`, see
+    /// `ParserOptions::reject_out_of_range`.
+    NumberOutOfRange {
+        pos: usize,
+    },
+    DuplicateKey {
+        key: String,
+        pos: usize,
+    },
+    DepthLimitExceeded {
+        pos: usize,
+    },
+    SizeLimitExceeded {
+        limit: usize,
+    },
+    /// An array or object longer than `ParserOptions::max_array_elements` or
+    /// `max_object_entries`, at the first element past the limit.
+    TooManyElements {
+        limit: usize,
+        pos: usize,
+    },
+    /// More work than `ParserOptions::max_steps` allows.
+    StepLimitExceeded {
+        limit: usize,
+    },
+    /// The progress callback asked to stop.
+    Cancelled {
+        pos: usize,
+    },
+}
+
+/// Line and column of a position in the input, both starting at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl LineColumn {
+    /// Converts a byte offset, counting each character as one column.
+    pub fn from_offset(input: &str, offset: usize) -> LineColumn {
+        LineColumn::from_offset_with_tab_width(input, offset, 1)
+    }
+
+    /// Converts a byte offset, counting a tab as `tab_width` columns the way
+    /// an editor would display it.
+    pub fn from_offset_with_tab_width(input: &str, offset: usize, tab_width: usize) -> LineColumn {
+        let mut line = 1;
+        let mut column = 1;
+        for (i, ch) in input.char_indices() {
+            if i >= offset {
+                break;
+            }
+            match ch {
+                '\n' => {
+                    line += 1;
+                    column = 1;
+                }
+                '\t' => column += tab_width,
+                _ => column += 1,
+            }
+        }
+        LineColumn { line, column }
+    }
+}
+
+impl ParseError {
+    /// Line and column of the error within `input`, which must be the text
+    /// that was parsed. End of input errors point just past the last character.
+    pub fn line_column(&self, input: &str) -> LineColumn {
+        self.line_column_with_tab_width(input, 1)
+    }
+
+    pub fn line_column_with_tab_width(&self, input: &str, tab_width: usize) -> LineColumn {
+        let offset = self.position().unwrap_or(input.len());
+        LineColumn::from_offset_with_tab_width(input, offset, tab_width)
+    }
+
+    /// The same error with its position moved `offset` bytes further, for an
+    /// error in a piece of a larger input.
+    pub(crate) fn offset_by(mut self, offset: usize) -> ParseError {
+        match &mut self {
+            ParseError::UnexpectedEof
+            | ParseError::SizeLimitExceeded { .. }
+            | ParseError::StepLimitExceeded { .. } => {}
+            ParseError::UnexpectedChar { pos, .. }
+            | ParseError::Expected { pos, .. }
+            | ParseError::ExpectedKey { pos }
+            | ParseError::TrailingCharacters { pos }
+            | ParseError::InvalidUtf8 { offset: pos }
+            | ParseError::InvalidEscape { pos }
+            | ParseError::InvalidNumber { pos }
+            | ParseError::LeadingPlus { pos }
+            | ParseError::MissingLeadingZero { pos, .. }
+            | ParseError::NumberOutOfRange { pos }
+            | ParseError::DuplicateKey { pos, .. }
+            | ParseError::DepthLimitExceeded { pos }
+            | ParseError::TooManyElements { pos, .. }
+            | ParseError::Cancelled { pos } => *pos += offset,
+        }
+        self
+    }
+
+    /// Byte offset the error points at, if it has one.
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            ParseError::UnexpectedEof
+            | ParseError::SizeLimitExceeded { .. }
+            | ParseError::StepLimitExceeded { .. } => None,
+            ParseError::UnexpectedChar { pos, .. }
+            | ParseError::Expected { pos, .. }
+            | ParseError::ExpectedKey { pos }
+            | ParseError::TrailingCharacters { pos }
+            | ParseError::InvalidUtf8 { offset: pos }
+            | ParseError::InvalidEscape { pos }
+            | ParseError::InvalidNumber { pos }
+            | ParseError::LeadingPlus { pos }
+            | ParseError::MissingLeadingZero { pos, .. }
+            | ParseError::NumberOutOfRange { pos }
+            | ParseError::DuplicateKey { pos, .. }
+            | ParseError::DepthLimitExceeded { pos }
+            | ParseError::TooManyElements { pos, .. }
+            | ParseError::Cancelled { pos } => Some(*pos),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEof => write!(f, "unexpected end of input"),
+            ParseError::UnexpectedChar { ch, pos } => {
+                write!(f, "unexpected character {:?} at position {}", ch, pos)
+            }
+            ParseError::Expected {
+                expected,
+                found,
+                pos,
+            } => write!(
+                f,
+                "expected {:?} but found {:?} at position {}",
+                expected, found, pos
+            ),
+            ParseError::ExpectedKey { pos } => {
+                write!(f, "expected a string key at position {}", pos)
+            }
+            ParseError::TrailingCharacters { pos } => {
+                write!(f, "trailing characters at position {}", pos)
+            }
+            ParseError::InvalidUtf8 { offset } => {
+                write!(f, "invalid UTF-8 at byte offset {}", offset)
+            }
+            ParseError::InvalidEscape { pos } => {
+                write!(f, "invalid escape sequence at position {}", pos)
+            }
+            ParseError::InvalidNumber { pos } => {
+                write!(f, "invalid number at position {}", pos)
+            }
+            ParseError::LeadingPlus { pos } => write!(
+                f,
+                "number with a leading '+' at position {}, JSON doesn't allow it: remove the '+'",
+                pos
+            ),
+            ParseError::MissingLeadingZero { fixed, pos } => write!(
+                f,
+                "number starting with '.' at position {}, JSON needs a digit first: write {} instead",
+                pos, fixed
+            ),
+            ParseError::NumberOutOfRange { pos } => {
+                write!(f, "number out of range at position {}", pos)
+            }
+            ParseError::DuplicateKey { key, pos } => {
+                write!(f, "duplicate key {:?} at position {}", key, pos)
+            }
+            ParseError::DepthLimitExceeded { pos } => {
+                write!(f, "nesting limit exceeded at position {}", pos)
+            }
+            ParseError::SizeLimitExceeded { limit } => {
+                write!(f, "input is larger than the limit of {} bytes", limit)
+            }
+            ParseError::TooManyElements { limit, pos } => write!(
+                f,
+                "container has more than the limit of {} elements at position {}",
+                limit, pos
+            ),
+            ParseError::StepLimitExceeded { limit } => {
+                write!(f, "parsing took more than the limit of {} steps", limit)
+            }
+            ParseError::Cancelled { pos } => write!(f, "parsing cancelled at position {}", pos),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Error from the entry points that do more than parse a string: read a
+/// file, or convert the result.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse(ParseError),
+    /// A parse error on one line of newline-delimited input. `line` counts
+    /// from 1 in the whole input and `column` from 1 within that line. The
+    /// error's own position is a byte offset into the whole input.
+    Line {
+        line: usize,
+        column: usize,
+        error: ParseError,
+    },
+    Type(TypeError),
+}
+
+/// IO errors can't be compared, two of them are equal if their kinds are.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Error::Io(a), Error::Io(b)) => a.kind() == b.kind(),
+            (Error::Parse(a), Error::Parse(b)) => a == b,
+            (
+                Error::Line {
+                    line: a_line,
+                    column: a_column,
+                    error: a,
+                },
+                Error::Line {
+                    line: b_line,
+                    column: b_column,
+                    error: b,
+                },
+            ) => a_line == b_line && a_column == b_column && a == b,
+            (Error::Type(a), Error::Type(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse(e) => write!(f, "{}", e),
+            Error::Line {
+                line,
+                column,
+                error,
+            } => write!(f, "line {}, column {}: {}", line, column, error),
+            Error::Type(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Parse(e) | Error::Line { error: e, .. } => Some(e),
+            Error::Type(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<TypeError> for Error {
+    fn from(e: TypeError) -> Self {
+        Error::Type(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_line_column() {
+        let input = "{\n\t\"a\" 1}";
+        let error = from_str(input).unwrap_err();
+        assert_eq!(error.position(), Some(7));
+        assert_eq!(error.line_column(input), LineColumn { line: 2, column: 6 });
+        assert_eq!(
+            error.line_column_with_tab_width(input, 4),
+            LineColumn { line: 2, column: 9 }
+        );
+
+        let input = "[1,\n";
+        assert_eq!(
+            from_str(input).unwrap_err().line_column(input),
+            LineColumn { line: 2, column: 1 }
+        );
+    }
+}
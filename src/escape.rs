@@ -0,0 +1,152 @@
+use std::fmt;
+
+use crate::ParseError;
+
+/// Quotes `s` as a JSON string, escaping quotes, backslashes and control
+/// characters.
+pub fn escape_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    write_quoted(&mut out, s).unwrap();
+    out
+}
+
+/// Decodes the escape sequences in the contents of a JSON string, given
+/// without its surrounding quotes.
+pub fn unescape_string(s: &str) -> Result<String, ParseError> {
+    let mut result = String::with_capacity(s.len());
+    let mut pos = 0;
+    while let Some(ch) = s[pos..].chars().next() {
+        match ch {
+            '\\' => {
+                let (decoded, len) =
+                    decode_escape(&s[pos + 1..]).ok_or(ParseError::InvalidEscape { pos })?;
+                result.push(decoded);
+                pos += 1 + len;
+            }
+            '"' => return Err(ParseError::UnexpectedChar { ch, pos }),
+            ch => {
+                result.push(ch);
+                pos += ch.len_utf8();
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Writes `s` as a quoted JSON string.
+pub(crate) fn write_quoted<W: fmt::Write>(out: &mut W, s: &str) -> fmt::Result {
+    write_quoted_with(out, s, false)
+}
+
+/// Like `write_quoted`, optionally escaping every non-ASCII character as
+/// `\uXXXX`, using a surrogate pair beyond the BMP.
+pub(crate) fn write_quoted_with<W: fmt::Write>(
+    out: &mut W,
+    s: &str,
+    escape_non_ascii: bool,
+) -> fmt::Result {
+    out.write_char('"')?;
+    for ch in s.chars() {
+        match ch {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{8}' => out.write_str("\\b")?,
+            '\u{c}' => out.write_str("\\f")?,
+            ch if ch < ' ' => write!(out, "\\u{:04x}", ch as u32)?,
+            ch if escape_non_ascii && !ch.is_ascii() => {
+                for unit in ch.encode_utf16(&mut [0; 2]) {
+                    write!(out, "\\u{:04x}", unit)?;
+                }
+            }
+            ch => out.write_char(ch)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Decodes the escape sequence at the start of `rest`, which follows a
+/// backslash. Returns the character and how many bytes of `rest` it used,
+/// or `None` if the sequence is invalid.
+pub(crate) fn decode_escape(rest: &str) -> Option<(char, usize)> {
+    let ch = match rest.chars().next()? {
+        '"' => '"',
+        '\\' => '\\',
+        '/' => '/',
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'u' => return decode_unicode(rest),
+        _ => return None,
+    };
+    Some((ch, 1))
+}
+
+/// `uXXXX`, or a `uXXXX\uXXXX` surrogate pair for characters beyond the BMP.
+fn decode_unicode(rest: &str) -> Option<(char, usize)> {
+    let high = hex4(rest.get(1..5)?)?;
+    if !(0xD800..0xDC00).contains(&high) {
+        return char::from_u32(high).map(|ch| (ch, 5));
+    }
+    if !rest[5..].starts_with("\\u") {
+        return None;
+    }
+    let low = hex4(rest.get(7..11)?)?;
+    if !(0xDC00..0xE000).contains(&low) {
+        return None;
+    }
+    let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+    char::from_u32(code).map(|ch| (ch, 11))
+}
+
+fn hex4(digits: &str) -> Option<u32> {
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JsonValue, from_str};
+
+    #[test]
+    fn test_escape_round_trip() {
+        let raw = "say \"hi\"\nto\t\\ caf\u{e9} \u{1F600} \u{1}";
+        let escaped = escape_string(raw);
+        assert_eq!(
+            escaped,
+            "\"say \\\"hi\\\"\\nto\\t\\\\ caf\u{e9} \u{1F600} \\u0001\""
+        );
+        assert_eq!(
+            unescape_string(&escaped[1..escaped.len() - 1]),
+            Ok(raw.to_string())
+        );
+        assert_eq!(from_str(&escaped), Ok(JsonValue::String(raw.to_string())));
+    }
+
+    #[test]
+    fn test_unescape_string() {
+        assert_eq!(
+            unescape_string(r"caf\u00e9 \ud83d\ude00 \/"),
+            Ok("caf\u{e9} \u{1F600} /".to_string())
+        );
+        assert_eq!(
+            unescape_string(r"ab\x"),
+            Err(ParseError::InvalidEscape { pos: 2 })
+        );
+        assert_eq!(
+            unescape_string(r"\ud83d alone"),
+            Err(ParseError::InvalidEscape { pos: 0 })
+        );
+        assert_eq!(
+            unescape_string(r#"a"b"#),
+            Err(ParseError::UnexpectedChar { ch: '"', pos: 1 })
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use crate::pointer::parse_index;
+use crate::{JsonValue, Map};
+
+impl JsonValue {
+    /// Flattens nested objects and arrays into a single map keyed by dotted
+    /// paths, e.g. `{"a": {"b": [1]}}` becomes `{"a.b.0": 1}`.
+    ///
+    /// A `.` or `\` inside a key is escaped with a `\`, so `{"a.b": 1}`
+    /// becomes `{"a\\.b": 1}` and can't be mistaken for `{"a": {"b": 1}}`.
+    /// Empty containers are kept as leaves so that `unflatten` can restore them.
+    pub fn flatten(&self) -> HashMap<String, JsonValue> {
+        let mut out = HashMap::new();
+        self.flatten_into(None, &mut out);
+        out
+    }
+
+    fn flatten_into(&self, prefix: Option<&str>, out: &mut HashMap<String, JsonValue>) {
+        match self {
+            JsonValue::Obj(map) if !map.is_empty() => {
+                for (key, value) in map {
+                    value.flatten_into(Some(&join(prefix, key)), out);
+                }
+            }
+            JsonValue::Array(arr) if !arr.is_empty() => {
+                for (i, value) in arr.iter().enumerate() {
+                    value.flatten_into(Some(&join(prefix, &i.to_string())), out);
+                }
+            }
+            leaf => {
+                out.insert(prefix.unwrap_or_default().to_string(), leaf.clone());
+            }
+        }
+    }
+
+    /// Rebuilds a nested value from dotted keys, the inverse of `flatten`.
+    ///
+    /// Keys are taken in sorted order, indices by their value, so the result
+    /// doesn't depend on the order of the map. A segment written like an
+    /// array index (digits, no leading zero) is one if it extends or falls
+    /// within the array built so far. Anything else is an object key, and
+    /// an array that gets a key, or an index past its end, becomes an object
+    /// keyed by the indices.
+    ///
+    /// `\.` and `\\` in a key stand for a `.` and a `\` in the segment, as
+    /// `flatten` writes them. A key that is also the start of a longer one,
+    /// like `a` next to `a.b`, loses: its value is dropped for the nesting.
+    pub fn unflatten(map: &HashMap<String, JsonValue>) -> JsonValue {
+        let mut entries: Vec<(Vec<String>, &JsonValue)> =
+            map.iter().map(|(key, value)| (split(key), value)).collect();
+        entries.sort_by_cached_key(|(segments, _)| {
+            segments
+                .iter()
+                .map(|s| parse_index(s).ok_or(s.clone()))
+                .collect::<Vec<_>>()
+        });
+        let mut root = JsonValue::Null;
+        for (segments, value) in entries {
+            insert_path(&mut root, &segments, value.clone());
+        }
+        match root {
+            JsonValue::Null => JsonValue::Obj(Map::new()),
+            root => root,
+        }
+    }
+}
+
+fn join(prefix: Option<&str>, key: &str) -> String {
+    let key = key.replace('\\', "\\\\").replace('.', "\\.");
+    match prefix {
+        Some(prefix) => format!("{}.{}", prefix, key),
+        None => key,
+    }
+}
+
+/// Splits a flattened key at the dots that aren't escaped.
+fn split(key: &str) -> Vec<String> {
+    let mut segments = vec![String::new()];
+    let mut chars = key.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => segments
+                .last_mut()
+                .unwrap()
+                .push(chars.next().unwrap_or('\\')),
+            '.' => segments.push(String::new()),
+            ch => segments.last_mut().unwrap().push(ch),
+        }
+    }
+    segments
+}
+
+fn insert_path(target: &mut JsonValue, segments: &[String], value: JsonValue) {
+    let Some((segment, rest)) = segments.split_first() else {
+        *target = value;
+        return;
+    };
+
+    let index = parse_index(segment).filter(|&i| match &*target {
+        JsonValue::Array(arr) => i <= arr.len(),
+        _ => i == 0,
+    });
+    match (&*target, index) {
+        (JsonValue::Obj(_), _) | (JsonValue::Array(_), Some(_)) => {}
+        // A key, or an index past the end, showed up next to indices, so the
+        // array has to become an object
+        (JsonValue::Array(_), None) => {
+            let JsonValue::Array(arr) = std::mem::replace(target, JsonValue::Null) else {
+                unreachable!()
+            };
+            let map = arr
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v))
+                .collect();
+            *target = JsonValue::Obj(map);
+        }
+        (_, Some(_)) => *target = JsonValue::Array(vec![]),
+        (_, None) => *target = JsonValue::Obj(Map::new()),
+    }
+
+    match (target, index) {
+        (JsonValue::Array(arr), Some(i)) => {
+            if i == arr.len() {
+                arr.push(JsonValue::Null);
+            }
+            insert_path(&mut arr[i], rest, value);
+        }
+        (JsonValue::Obj(map), _) => {
+            let child = map.entry(segment.clone()).or_insert(JsonValue::Null);
+            insert_path(child, rest, value);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_unflatten() {
+        let mut flat = HashMap::new();
+        flat.insert("a.b".to_string(), JsonValue::Number(1.0));
+        flat.insert("a.c".to_string(), JsonValue::Number(2.0));
+
+        let mut inner = Map::new();
+        inner.insert("b".to_string(), JsonValue::Number(1.0));
+        inner.insert("c".to_string(), JsonValue::Number(2.0));
+        let mut expected = Map::new();
+        expected.insert("a".to_string(), JsonValue::Obj(inner));
+
+        assert_eq!(JsonValue::unflatten(&flat), JsonValue::Obj(expected));
+    }
+
+    #[test]
+    fn test_flatten_round_trip() {
+        let mut parser = Parser::new(
+            r#"{"one": 2, "two": [2, null, {"x": false}], "three": {"four": "4", "five": []}}"#
+                .to_string(),
+        );
+        let value = parser.parse_value().unwrap();
+
+        let flat = value.flatten();
+        assert_eq!(flat["two.2.x"], JsonValue::Bool(false));
+        assert_eq!(flat["three.five"], JsonValue::Array(vec![]));
+        assert_eq!(
+            JsonValue::unflatten(&flat).to_string(),
+            r#"{"one":2,"three":{"five":[],"four":"4"},"two":[2,null,{"x":false}]}"#
+        );
+
+        let long = JsonValue::Array((0..12).map(|i| JsonValue::Number(i as f64)).collect());
+        assert_eq!(
+            JsonValue::unflatten(&long.flatten()).to_string(),
+            "[0,1,2,3,4,5,6,7,8,9,10,11]"
+        );
+    }
+
+    #[test]
+    fn test_unflatten_odd_indices() {
+        let unflatten = |keys: &[&str]| {
+            let flat: HashMap<String, JsonValue> = keys
+                .iter()
+                .enumerate()
+                .map(|(i, k)| (k.to_string(), JsonValue::Number(i as f64)))
+                .collect();
+            JsonValue::unflatten(&flat).to_string()
+        };
+        assert_eq!(
+            unflatten(&["a.18446744073709551615"]),
+            r#"{"a":{"18446744073709551615":0}}"#
+        );
+        assert_eq!(
+            unflatten(&["a.99999999999999999999999"]),
+            r#"{"a":{"99999999999999999999999":0}}"#
+        );
+        assert_eq!(
+            unflatten(&["a.0", "a.1000000000000"]),
+            r#"{"a":{"0":0,"1000000000000":1}}"#
+        );
+        assert_eq!(unflatten(&["a.+1"]), r#"{"a":{"+1":0}}"#);
+        assert_eq!(
+            unflatten(&["a.0", "a.01", "a.1"]),
+            r#"{"a":{"0":0,"1":2,"01":1}}"#
+        );
+        assert_eq!(unflatten(&["a.0", "a.2"]), r#"{"a":{"0":0,"2":1}}"#);
+        // The longer path wins over a leaf at its start
+        assert_eq!(unflatten(&["a.b", "a"]), r#"{"a":{"b":0}}"#);
+        assert_eq!(unflatten(&["a", "a.0"]), r#"{"a":[1]}"#);
+    }
+
+    #[test]
+    fn test_flatten_escapes_dots() {
+        let dotted = Parser::new(r#"{"a.b": 1, "c\\": {"": 2}, "": {"d": 3}}"#.to_string())
+            .parse_value()
+            .unwrap();
+        let nested = Parser::new(r#"{"a": {"b": 1}}"#.to_string())
+            .parse_value()
+            .unwrap();
+        let flat = dotted.flatten();
+        assert_eq!(flat["a\\.b"], JsonValue::Number(1.0));
+        assert_eq!(flat["c\\\\."], JsonValue::Number(2.0));
+        assert_eq!(flat[".d"], JsonValue::Number(3.0));
+        assert_ne!(flat, nested.flatten());
+        assert_eq!(JsonValue::unflatten(&flat), dotted);
+        assert_eq!(JsonValue::unflatten(&nested.flatten()), nested);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::tokenizer::{Span, Token, Tokenizer};
+use crate::{ParseError, ParserOptions, from_str_with_options};
+
+/// A token along with the trivia (whitespace and comments) written before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriviaToken {
+    pub leading: String,
+    pub token: Token,
+    pub span: Span,
+}
+
+/// Tokenizes `input` keeping the trivia between tokens, so that the original
+/// text can be rebuilt exactly. Also returns the trivia after the last token.
+/// A `#!` line skipped under `ParserOptions::shebang` is part of the first
+/// token's trivia.
+pub fn tokenize_with_trivia(
+    input: &str,
+    options: ParserOptions,
+) -> Result<(Vec<TriviaToken>, String), ParseError> {
+    let mut tokenizer = Tokenizer::with_options(input.to_string(), options);
+    let mut tokens = vec![];
+    // Not the tokenizer's position, which is already past a shebang
+    let mut before = 0;
+    loop {
+        match tokenizer.next_token()? {
+            Some((token, span)) => tokens.push(TriviaToken {
+                leading: input[before..span.start].to_string(),
+                token,
+                span,
+            }),
+            None => return Ok((tokens, input[before..].to_string())),
+        }
+        before = tokenizer.position();
+    }
+}
+
+/// Pretty-prints `input` with two space indentation while keeping what the
+/// author wrote on purpose: blank lines between members, comments, and the
+/// exact text of every string and number. A comment that trailed a member
+/// on the same line moves onto its own line before the next one.
+pub fn reformat(input: &str, options: ParserOptions) -> Result<String, ParseError> {
+    // Make sure the structure is valid before trusting the token stream
+    from_str_with_options(input, options.clone())?;
+    let (tokens, trailing) = tokenize_with_trivia(input, options)?;
+
+    let mut out = String::with_capacity(input.len());
+    let mut depth = 0;
+    let mut line_break = false;
+    let mut iter = tokens.iter().peekable();
+    while let Some(tt) = iter.next() {
+        if matches!(tt.token, Token::RBrace | Token::RBracket) {
+            depth -= 1;
+            line_break = true;
+        }
+        let mut at_line_start = line_break || out.is_empty();
+        if line_break {
+            out.push('\n');
+        }
+        for piece in trivia(&tt.leading) {
+            match piece {
+                Piece::BlankLine => {
+                    if at_line_start && !out.is_empty() && !out.ends_with("\n\n") {
+                        out.push('\n');
+                    }
+                }
+                Piece::Comment { text: comment, .. } if at_line_start => {
+                    push_indent(&mut out, depth);
+                    out.push_str(comment);
+                    out.push('\n');
+                }
+                // A comment in the middle of a line stays there, line
+                // comments still need the rest of the line to themselves
+                Piece::Comment { text: comment, .. } => {
+                    out.push(' ');
+                    out.push_str(comment);
+                    if comment.starts_with("/*") {
+                        out.push(' ');
+                    } else {
+                        out.push('\n');
+                        at_line_start = true;
+                    }
+                }
+            }
+        }
+        if at_line_start {
+            push_indent(&mut out, depth);
+        }
+        line_break = false;
+
+        match tt.token {
+            Token::LBrace | Token::LBracket => {
+                let close = match tt.token {
+                    Token::LBrace => Token::RBrace,
+                    _ => Token::RBracket,
+                };
+                out.push_str(&input[tt.span.start..tt.span.end]);
+                // Keep empty containers on one line unless they hold a comment
+                match iter.peek() {
+                    Some(next) if next.token == close && next.leading.trim().is_empty() => {
+                        out.push_str(&input[next.span.start..next.span.end]);
+                        iter.next();
+                    }
+                    _ => {
+                        depth += 1;
+                        line_break = true;
+                    }
+                }
+            }
+            Token::Comma => {
+                out.push(',');
+                line_break = true;
+            }
+            Token::Colon => out.push_str(": "),
+            _ => out.push_str(&input[tt.span.start..tt.span.end]),
+        }
+    }
+
+    for piece in trivia(&trailing) {
+        if let Piece::Comment { text: comment, .. } = piece {
+            out.push('\n');
+            out.push_str(comment);
+        }
+    }
+    Ok(out)
+}
+
+fn push_indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+/// What matters in a run of trivia when reformatting.
+pub(crate) enum Piece<'a> {
+    /// An empty line separates what came before from what follows.
+    BlankLine,
+    /// A comment starting `offset` bytes into the trivia.
+    Comment { offset: usize, text: &'a str },
+}
+
+/// Splits trivia into its comments and blank lines, in order. `text` is
+/// known to hold only whitespace and complete comments, the tokenizer already
+/// checked it.
+pub(crate) fn trivia(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = vec![];
+    let mut newlines = 0;
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        let len = if rest.starts_with("/*") {
+            rest.find("*/").map_or(rest.len(), |end| end + 2)
+        } else if rest.starts_with("//") || ch == '#' {
+            rest.find('\n').unwrap_or(rest.len())
+        } else {
+            if ch == '\n' {
+                newlines += 1;
+                if newlines == 2 {
+                    pieces.push(Piece::BlankLine);
+                }
+            }
+            rest = &rest[ch.len_utf8()..];
+            continue;
+        };
+        pieces.push(Piece::Comment {
+            offset: text.len() - rest.len(),
+            text: &rest[..len],
+        });
+        rest = &rest[len..];
+        newlines = 0;
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommentStyle;
+
+    #[test]
+    fn test_tokenize_with_trivia() {
+        let input = " [1 ,\n\t2] \n";
+        let (tokens, trailing) = tokenize_with_trivia(input, ParserOptions::default()).unwrap();
+        let rebuilt: String = tokens
+            .iter()
+            .map(|t| format!("{}{}", t.leading, &input[t.span.start..t.span.end]))
+            .collect();
+        assert_eq!(rebuilt + &trailing, input);
+        assert_eq!(tokens[2].leading, " ");
+        assert_eq!(tokens[3].leading, "\n\t");
+    }
+
+    #[test]
+    fn test_reformat_keeps_blank_lines() {
+        let input = "{\"a\": 1.50,\n\n  \"b\": [1,2], \"c\": {},\n\n\n\"d\": {\"e\": \"x\\n\"}}";
+        assert_eq!(
+            reformat(input, ParserOptions::default()).unwrap(),
+            "{\n  \"a\": 1.50,\n\n  \"b\": [\n    1,\n    2\n  ],\n  \"c\": {},\n\n  \"d\": {\n    \"e\": \"x\\n\"\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn test_reformat_keeps_shebang() {
+        let options = ParserOptions {
+            shebang: true,
+            ..Default::default()
+        };
+        let input = "#!/usr/bin/env run-config
+{\"a\": [1]}\n";
+        let (tokens, trailing) = tokenize_with_trivia(input, options.clone()).unwrap();
+        assert_eq!(tokens[0].leading, "#!/usr/bin/env run-config\n");
+        assert_eq!(trailing, "\n");
+        assert_eq!(
+            reformat(input, options).unwrap(),
+            "#!/usr/bin/env run-config\n{\n  \"a\": [\n    1\n  ]\n}"
+        );
+    }
+
+    #[test]
+    fn test_reformat_keeps_comments() {
+        let options = ParserOptions {
+            comments: CommentStyle::ALL,
+            ..Default::default()
+        };
+        let input = "// config\n{\"a\": 1, # first\n\n/* second */ \"b\": 2}";
+        assert_eq!(
+            reformat(input, options).unwrap(),
+            "// config\n{\n  \"a\": 1,\n  # first\n\n  /* second */\n  \"b\": 2\n}"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use crate::tokenizer::{Span, Token, Tokenizer};
+use crate::{JsonValue, ParseError, ParserOptions, from_str_with_options};
+
+/// A top-level array whose elements are only parsed when asked for.
+///
+/// Creating one tokenizes the input once to record where every element
+/// starts and ends, so malformed elements are only reported by `get`, with
+/// positions relative to the element.
+#[derive(Debug)]
+pub struct LazyArray {
+    src: String,
+    spans: Vec<Span>,
+    options: ParserOptions,
+}
+
+impl LazyArray {
+    pub fn new(src: String) -> Result<LazyArray, ParseError> {
+        LazyArray::with_options(src, ParserOptions::default())
+    }
+
+    pub fn with_options(src: String, options: ParserOptions) -> Result<LazyArray, ParseError> {
+        let mut tokens = Tokenizer::with_options(src, options.clone());
+        let unexpected =
+            |tokens: &Tokenizer, span: Span| match tokens.source()[span.start..].chars().next() {
+                Some(ch) => ParseError::UnexpectedChar {
+                    ch,
+                    pos: span.start,
+                },
+                None => ParseError::UnexpectedEof,
+            };
+
+        match tokens.next_token()? {
+            Some((Token::LBracket, _)) => {}
+            Some((_, span)) => return Err(unexpected(&tokens, span)),
+            None => return Err(ParseError::UnexpectedEof),
+        }
+
+        let mut spans = vec![];
+        let mut depth = 0;
+        let mut element: Option<Span> = None;
+        loop {
+            let (token, span) = tokens.next_token()?.ok_or(ParseError::UnexpectedEof)?;
+            match token {
+                Token::LBrace | Token::LBracket => depth += 1,
+                Token::RBrace | Token::RBracket if depth > 0 => depth -= 1,
+                Token::Comma | Token::RBracket if depth == 0 => {
+                    match element.take() {
+                        Some(element) => spans.push(element),
+                        // `[]`, or a trailing comma when those are allowed
+                        None if token == Token::RBracket
+                            && (spans.is_empty() || options.trailing_commas) => {}
+                        None => return Err(unexpected(&tokens, span)),
+                    }
+                    if token == Token::RBracket {
+                        break;
+                    }
+                    continue;
+                }
+                Token::RBrace => return Err(unexpected(&tokens, span)),
+                _ => {}
+            }
+            element = Some(match element {
+                Some(element) => Span {
+                    start: element.start,
+                    end: span.end,
+                },
+                None => span,
+            });
+        }
+        if !tokens.is_at_end() {
+            return Err(ParseError::TrailingCharacters {
+                pos: tokens.position(),
+            });
+        }
+
+        Ok(LazyArray {
+            src: tokens.into_source(),
+            spans,
+            options,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Parses element `i`, or returns `None` if it is out of bounds.
+    pub fn get(&self, i: usize) -> Option<Result<JsonValue, ParseError>> {
+        let span = self.spans.get(i)?;
+        Some(from_str_with_options(
+            &self.src[span.start..span.end],
+            self.options.clone(),
+        ))
+    }
+
+    /// The raw text of element `i`.
+    pub fn get_raw(&self, i: usize) -> Option<&str> {
+        let span = self.spans.get(i)?;
+        Some(&self.src[span.start..span.end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_lazy_array() {
+        let items: Vec<String> = (0..1000)
+            .map(|i| format!(r#"{{"id": {}, "tags": ["t{}", [], {{}}]}}"#, i, i))
+            .collect();
+        let input = format!("[{}]", items.join(",\n"));
+
+        let lazy = LazyArray::new(input).unwrap();
+        assert_eq!(lazy.len(), 1000);
+        for i in [0, 417, 999] {
+            assert_eq!(lazy.get(i), Some(from_str(&items[i])));
+        }
+        assert_eq!(lazy.get(1000), None);
+        assert_eq!(lazy.get_raw(3), Some(items[3].as_str()));
+    }
+
+    #[test]
+    fn test_lazy_array_boundaries() {
+        assert!(LazyArray::new(" [ ] ".to_string()).unwrap().is_empty());
+        assert_eq!(LazyArray::new("[1, 2,]".to_string()).unwrap().len(), 2);
+
+        let lazy = LazyArray::new("[1, {\"a\" 2}]".to_string()).unwrap();
+        assert_eq!(lazy.get(0), Some(Ok(JsonValue::Number(1.0))));
+        assert!(matches!(lazy.get(1), Some(Err(_))));
+
+        assert_eq!(
+            LazyArray::new("[1,,2]".to_string()).unwrap_err(),
+            ParseError::UnexpectedChar { ch: ',', pos: 3 }
+        );
+        assert_eq!(
+            LazyArray::new("{}".to_string()).unwrap_err(),
+            ParseError::UnexpectedChar { ch: '{', pos: 0 }
+        );
+        assert_eq!(
+            LazyArray::new("[1] 2".to_string()).unwrap_err(),
+            ParseError::TrailingCharacters { pos: 4 }
+        );
+        assert_eq!(
+            LazyArray::new("[1, 2".to_string()).unwrap_err(),
+            ParseError::UnexpectedEof
+        );
+    }
+}
@@ -0,0 +1,450 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+
+mod config;
+mod convert;
+mod error;
+mod escape;
+mod flatten;
+mod format;
+mod lazy;
+mod lint;
+mod options;
+mod order;
+mod parser;
+mod patch;
+mod pointer;
+mod serialize;
+mod shape;
+mod shared;
+mod stats;
+mod stream;
+mod tokenizer;
+#[cfg(feature = "toml")]
+mod toml_value;
+mod validate;
+mod value;
+mod visit;
+
+pub use config::{Config, ConfigError, FromConfig, IntoConfig};
+pub use convert::{FromJson, GetError, TypeError};
+pub use error::{Error, LineColumn, ParseError};
+pub use escape::{escape_string, unescape_string};
+pub use format::{TriviaToken, reformat, tokenize_with_trivia};
+pub use lazy::LazyArray;
+pub use lint::{Lint, from_str_with_lints};
+pub use options::{BigIntegers, CommentStyle, DuplicateKeyPolicy, ParserOptions};
+pub use order::cmp_json;
+pub use parser::Parser;
+pub use patch::PatchError;
+pub use pointer::{PathSegment, SetError};
+pub use serialize::{FloatFormat, LineEnding, SerializeOptions};
+pub use shape::{Shape, ShapeError};
+pub use shared::SharedJsonValue;
+pub use stats::Stats;
+pub use stream::StreamingParser;
+pub use tokenizer::{Span, Token, Tokenizer};
+#[cfg(feature = "toml")]
+pub use toml_value::TomlError;
+pub use validate::{Validator, validate_reader};
+pub use value::{FieldState, JsonValue, Map};
+pub use visit::Visitor;
+
+/// Parses a complete JSON document, rejecting anything after the value.
+pub fn from_str(input: &str) -> Result<JsonValue, ParseError> {
+    from_str_with_options(input, ParserOptions::default())
+}
+
+pub fn from_str_with_options(input: &str, options: ParserOptions) -> Result<JsonValue, ParseError> {
+    let mut parser = Parser::with_options(input.to_string(), options);
+    let value = parser.parse_value()?;
+    parser.finish()?;
+    Ok(value)
+}
+
+/// Parses one value from the start of `input` and returns it with whatever
+/// follows, untouched, for input where JSON is embedded in something else.
+pub fn from_prefix(input: &str) -> Result<(JsonValue, &str), ParseError> {
+    let mut parser = Parser::new(input.to_string());
+    let value = parser.parse_value()?;
+    Ok((value, &input[parser.position()..]))
+}
+
+/// Like `from_str` but for raw bytes, which must be valid UTF-8.
+pub fn from_slice(bytes: &[u8]) -> Result<JsonValue, ParseError> {
+    let input = std::str::from_utf8(bytes).map_err(|e| ParseError::InvalidUtf8 {
+        offset: e.valid_up_to(),
+    })?;
+    from_str(input)
+}
+
+/// Like `from_slice`, but invalid UTF-8 is replaced with U+FFFD as by
+/// `String::from_utf8_lossy` instead of failing. Error positions are then
+/// offsets into the repaired text.
+pub fn from_slice_lossy(bytes: &[u8]) -> Result<JsonValue, ParseError> {
+    from_str(&String::from_utf8_lossy(bytes))
+}
+
+/// Reads the file at `path` and parses it as a complete JSON document. Parse
+/// errors come as `Error::Line` with the line and column in the file.
+pub fn from_file<P: AsRef<Path>>(path: P) -> Result<JsonValue, Error> {
+    let input = std::fs::read_to_string(path)?;
+    from_str(&input).map_err(|error| {
+        let at = error.line_column(&input);
+        Error::Line {
+            line: at.line,
+            column: at.column,
+            error,
+        }
+    })
+}
+
+/// Parses like `from_str`, then passes every entry through `reviver` the way
+/// JavaScript's `JSON.parse` does: bottom-up, with the object key or the
+/// array index as a string, and `""` for the root. Whatever the reviver
+/// returns replaces the entry.
+pub fn from_str_with_reviver<F>(input: &str, mut reviver: F) -> Result<JsonValue, ParseError>
+where
+    F: FnMut(&str, JsonValue) -> JsonValue,
+{
+    let value = from_str(input)?;
+    Ok(revive("", value, &mut reviver))
+}
+
+fn revive<F>(key: &str, value: JsonValue, reviver: &mut F) -> JsonValue
+where
+    F: FnMut(&str, JsonValue) -> JsonValue,
+{
+    let value = match value {
+        JsonValue::Array(arr) => JsonValue::Array(
+            arr.into_iter()
+                .enumerate()
+                .map(|(i, item)| revive(&i.to_string(), item, reviver))
+                .collect(),
+        ),
+        JsonValue::Obj(map) => JsonValue::Obj(
+            map.into_iter()
+                .map(|(k, item)| {
+                    let item = revive(&k, item, reviver);
+                    (k, item)
+                })
+                .collect(),
+        ),
+        other => other,
+    };
+    reviver(key, value)
+}
+
+/// Parses newline-delimited JSON one line at a time as the iterator is
+/// advanced, so only the current line is held in memory. Blank lines are
+/// skipped. Parse errors come as `Error::Line`: the line in the whole input,
+/// the column within that line, and the error's position as a byte offset
+/// into the whole input.
+pub fn from_reader_lines<R: BufRead>(
+    mut reader: R,
+) -> impl Iterator<Item = Result<JsonValue, Error>> {
+    let mut buffer = String::new();
+    let mut line = 0;
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        loop {
+            buffer.clear();
+            let start = offset;
+            match reader.read_line(&mut buffer) {
+                Ok(0) => return None,
+                Ok(len) => {
+                    line += 1;
+                    offset += len;
+                }
+                Err(e) => return Some(Err(e.into())),
+            }
+            let text = buffer.strip_suffix('\n').unwrap_or(&buffer);
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            if text.trim().is_empty() {
+                continue;
+            }
+            return Some(from_str(text).map_err(|error| Error::Line {
+                line,
+                column: error.line_column(text).column,
+                error: error.offset_by(start),
+            }));
+        }
+    })
+}
+
+/// Parses an object whose values all convert to `T`.
+pub fn from_str_map<T: FromJson>(input: &str) -> Result<HashMap<String, T>, Error> {
+    match from_str(input)? {
+        JsonValue::Obj(map) => map
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), T::from_json(v)?)))
+            .collect(),
+        other => Err(TypeError::new("object", &other).into()),
+    }
+}
+
+/// Parses an array whose elements all convert to `T`.
+pub fn from_str_vec<T: FromJson>(input: &str) -> Result<Vec<T>, Error> {
+    match from_str(input)? {
+        JsonValue::Array(arr) => arr.iter().map(|v| Ok(T::from_json(v)?)).collect(),
+        other => Err(TypeError::new("array", &other).into()),
+    }
+}
+
+/// Parses values written back to back, like `{"a":1}{"b":2}`, with only
+/// optional whitespace between them. Stops after the first error since there
+/// is no reliable way to find where the next value starts.
+pub fn from_str_concat(input: &str) -> Vec<Result<JsonValue, ParseError>> {
+    let mut parser = Parser::new(input.to_string());
+    let mut values = vec![];
+    while !parser.is_at_end() {
+        let value = parser.parse_value();
+        let failed = value.is_err();
+        values.push(value);
+        if failed {
+            break;
+        }
+    }
+    values
+}
+
+/// Best-effort parse for incomplete or broken input, see
+/// `Parser::parse_recovering`. Always returns a tree, along with every error
+/// that had to be worked around.
+pub fn parse_partial(input: &str) -> (JsonValue, Vec<ParseError>) {
+    Parser::new(input.to_string()).parse_recovering()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_slice() {
+        assert_eq!(from_slice(b"[1, null]"), from_str("[1, null]"));
+        assert_eq!(
+            from_slice(b"[1] x"),
+            Err(ParseError::TrailingCharacters { pos: 4 })
+        );
+    }
+
+    #[test]
+    fn test_from_slice_lossy() {
+        assert_eq!(
+            from_slice_lossy(b"[\"ab\xffc\", 1]"),
+            Ok(JsonValue::Array(vec![
+                JsonValue::String("ab\u{fffd}c".to_string()),
+                JsonValue::Number(1.0),
+            ]))
+        );
+        assert_eq!(from_slice_lossy(b"[1, null]"), from_str("[1, null]"));
+        // Outside of a string the replacement is still a stray character
+        assert_eq!(
+            from_slice_lossy(b"[\xff]"),
+            Err(ParseError::UnexpectedChar {
+                ch: '\u{fffd}',
+                pos: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_prefix() {
+        assert_eq!(
+            from_prefix("{} trailing"),
+            Ok((JsonValue::Obj(Map::new()), " trailing"))
+        );
+        assert_eq!(
+            from_prefix(" [1]]"),
+            Ok((JsonValue::Array(vec![JsonValue::Number(1.0)]), "]"))
+        );
+        assert_eq!(from_prefix("12ab"), Ok((JsonValue::Number(12.0), "ab")));
+        assert_eq!(from_prefix("  "), Err(ParseError::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_from_reader_lines() {
+        let input = "{\"a\": 1}\n\n[true]\n\"x\"\n{oops}\n";
+        let mut values = from_reader_lines(std::io::Cursor::new(input));
+        assert_eq!(values.next().unwrap(), Ok(from_str(r#"{"a": 1}"#).unwrap()));
+        assert_eq!(
+            values.next().unwrap(),
+            Ok(JsonValue::Array(vec![JsonValue::Bool(true)]))
+        );
+        assert_eq!(
+            values.next().unwrap(),
+            Ok(JsonValue::String("x".to_string()))
+        );
+        let error = values.next().unwrap().unwrap_err();
+        assert_eq!(
+            error,
+            Error::Line {
+                line: 5,
+                column: 2,
+                error: ParseError::UnexpectedChar { ch: 'o', pos: 22 }
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 5, column 2: unexpected character 'o' at position 22"
+        );
+        assert_eq!(&input[22..23], "o");
+        assert!(values.next().is_none());
+
+        let input = "[1, 2]\r\n{\"a\": 1,, \"b\": 2}\r\n[3]\r\n";
+        let mut values = from_reader_lines(std::io::Cursor::new(input));
+        assert!(values.next().unwrap().is_ok());
+        match values.next().unwrap() {
+            Err(Error::Line {
+                line,
+                column,
+                error,
+            }) => {
+                assert_eq!((line, column), (2, 9));
+                assert_eq!(error.position(), Some(16));
+                assert_eq!(&input[16..17], ",");
+            }
+            other => panic!("Expected a line error, got {:?}", other),
+        }
+        assert!(values.next().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join(format!("json_from_file_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"a": [1, 2]}"#).unwrap();
+        let value = from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(value, Ok(from_str(r#"{"a": [1, 2]}"#).unwrap()));
+
+        std::fs::write(&path, "{\n  \"a\": [1 2]\n}").unwrap();
+        let value = from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            value,
+            Err(Error::Line {
+                line: 2,
+                column: 11,
+                error: ParseError::Expected {
+                    expected: ']',
+                    found: '2',
+                    pos: 12
+                }
+            })
+        );
+
+        match from_file(&path) {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+            other => panic!("Expected an IO error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_from_str_with_reviver() {
+        let mut keys = vec![];
+        let value = from_str_with_reviver(
+            r#"{"a": [1, 2.5], "b": {"c": 3}, "d": "x"}"#,
+            |key, value| {
+                keys.push(key.to_string());
+                match value {
+                    JsonValue::Number(n) => JsonValue::Number(n * 2.0),
+                    other => other,
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            from_str(r#"{"a": [2, 5], "b": {"c": 6}, "d": "x"}"#).unwrap()
+        );
+        assert_eq!(keys, ["0", "1", "a", "c", "b", "d", ""]);
+    }
+
+    #[test]
+    fn test_from_str_map() {
+        let map = from_str_map::<f64>(r#"{"a":1,"b":2}"#).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a"], 1.0);
+        assert_eq!(map["b"], 2.0);
+
+        assert_eq!(
+            from_str_map::<f64>(r#"{"a":1,"b":"2"}"#),
+            Err(Error::Type(TypeError {
+                expected: "number",
+                found: "string"
+            }))
+        );
+        assert_eq!(
+            from_str_map::<f64>("[1]"),
+            Err(Error::Type(TypeError {
+                expected: "object",
+                found: "array"
+            }))
+        );
+    }
+
+    #[test]
+    fn test_from_str_vec() {
+        assert_eq!(from_str_vec::<f64>("[1,2,3]"), Ok(vec![1.0, 2.0, 3.0]));
+        assert_eq!(
+            from_str_vec::<String>(r#"["a","b"]"#),
+            Ok(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            from_str_vec::<String>(r#"["a",2]"#),
+            Err(Error::Type(TypeError {
+                expected: "string",
+                found: "number"
+            }))
+        );
+    }
+
+    #[test]
+    fn test_from_str_concat() {
+        let values = from_str_concat(r#"{"a":1}{"b":2} [3]"#);
+        assert_eq!(
+            values,
+            vec![
+                from_str(r#"{"a":1}"#),
+                from_str(r#"{"b":2}"#),
+                from_str("[3]")
+            ]
+        );
+
+        let values = from_str_concat(r#"{"a":1}{"b" 2}{"c":3}"#);
+        assert_eq!(values.len(), 2);
+        assert!(values[0].is_ok());
+        assert_eq!(
+            values[1],
+            Err(ParseError::Expected {
+                expected: ':',
+                found: '2',
+                pos: 12
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_partial() {
+        let (value, errors) = parse_partial(r#"{"a": 1, "b":"#);
+        assert_eq!(value.get("a"), Some(&JsonValue::Number(1.0)));
+        assert_eq!(value.get("b"), Some(&JsonValue::Null));
+        assert_eq!(errors, vec![ParseError::UnexpectedEof]);
+    }
+
+    #[test]
+    fn test_from_slice_invalid_utf8_offset() {
+        let bytes = b"[\"ab\xffc\"]";
+        assert_eq!(
+            from_slice(bytes),
+            Err(ParseError::InvalidUtf8 { offset: 4 })
+        );
+
+        let bytes = b"\"abcd\xc3\x28\"";
+        assert_eq!(
+            from_slice(bytes),
+            Err(ParseError::InvalidUtf8 { offset: 5 })
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use crate::format::{Piece, trivia};
+use crate::tokenizer::Token;
+use crate::{
+    CommentStyle, JsonValue, ParseError, ParserOptions, from_str_with_options, tokenize_with_trivia,
+};
+
+/// Something outside of plain JSON that was accepted anyway.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+    TrailingComma { pos: usize },
+    Comment { pos: usize },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Lint::TrailingComma { pos } => write!(f, "trailing comma at position {}", pos),
+            Lint::Comment { pos } => write!(f, "comment at position {}", pos),
+        }
+    }
+}
+
+/// Parses `input` allowing comments and trailing commas, and lists every
+/// place one was used so they can be flagged instead of rejected.
+/// Anything else outside of plain JSON is still an error.
+pub fn from_str_with_lints(input: &str) -> Result<(JsonValue, Vec<Lint>), ParseError> {
+    let options = ParserOptions {
+        comments: CommentStyle::ALL,
+        trailing_commas: true,
+        ..ParserOptions::strict()
+    };
+    let value = from_str_with_options(input, options.clone())?;
+    let (tokens, trailing) = tokenize_with_trivia(input, options)?;
+
+    let mut lints = vec![];
+    let mut previous: Option<(&Token, usize)> = None;
+    for tt in &tokens {
+        push_comments(&tt.leading, tt.span.start - tt.leading.len(), &mut lints);
+        if let Some((Token::Comma, pos)) = previous
+            && matches!(tt.token, Token::RBrace | Token::RBracket)
+        {
+            lints.push(Lint::TrailingComma { pos });
+        }
+        previous = Some((&tt.token, tt.span.start));
+    }
+    push_comments(&trailing, input.len() - trailing.len(), &mut lints);
+    lints.sort_by_key(|lint| match lint {
+        Lint::TrailingComma { pos } | Lint::Comment { pos } => *pos,
+    });
+    Ok((value, lints))
+}
+
+/// Adds a lint for each comment in `leading`, trivia that starts at byte
+/// `start` of the input.
+fn push_comments(leading: &str, start: usize, lints: &mut Vec<Lint>) {
+    for piece in trivia(leading) {
+        if let Piece::Comment { offset, .. } = piece {
+            lints.push(Lint::Comment {
+                pos: start + offset,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_lints() {
+        let input = "// config\n{\"a\": [1, 2,], /* b */ \"b\": 3,\n} # end";
+        let (value, lints) = from_str_with_lints(input).unwrap();
+        assert_eq!(value, from_str(r#"{"a": [1, 2], "b": 3}"#).unwrap());
+        assert_eq!(
+            lints,
+            vec![
+                Lint::Comment { pos: 0 },
+                Lint::TrailingComma { pos: 21 },
+                Lint::Comment { pos: 25 },
+                Lint::TrailingComma { pos: 39 },
+                Lint::Comment { pos: 43 },
+            ]
+        );
+        assert_eq!(lints[1].to_string(), "trailing comma at position 21");
+
+        assert_eq!(
+            from_str_with_lints("[1]"),
+            Ok((from_str("[1]").unwrap(), vec![]))
+        );
+        assert!(from_str_with_lints("['a']").is_err());
+        assert!(from_str_with_lints(r#"{"a": 1, "a": 2}"#).is_err());
+    }
+}
@@ -1,372 +1,189 @@
-use std::fmt;
-use std::{collections::HashMap, fs};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
 
-#[derive(Debug, PartialEq)]
-enum JsonValue {
-    Null,
-    Number(f64),
-    String(String),
-    Bool(bool),
-    Array(Vec<JsonValue>),
-    Obj(HashMap<String, JsonValue>),
-}
-
-impl fmt::Display for JsonValue {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            JsonValue::Null => write!(f, "null"),
-            JsonValue::Bool(b) => write!(f, "{}", b),
-            JsonValue::Number(n) => write!(f, "{}", n),
-            JsonValue::String(s) => write!(f, "\"{}\"", s),
-            JsonValue::Array(arr) => {
-                write!(f, "[")?;
-                for (i, item) in arr.iter().enumerate() {
-                    if i > 0 {
-                        write!(f, ",")?;
-                    }
-                    write!(f, "{}", item)?;
-                }
-                write!(f, "]")
-            }
-            JsonValue::Obj(map) => {
-                write!(f, "{{")?;
-                let mut first = true;
-                for (key, value) in map.iter() {
-                    if !first {
-                        write!(f, ",")?;
-                    }
-                    write!(f, "\"{}\":{}", key, value)?;
-                    first = false;
-                }
-                write!(f, "}}")
-            }
-        }
-    }
-}
-
-#[derive(Debug)]
-struct Parser {
-    src: String,
-    pos: usize,
-}
-
-impl Parser {
-    fn new(src: String) -> Parser {
-        Parser { src, pos: 0 }
-    }
-
-    fn parse(&mut self) -> Option<JsonValue> {
-        self.skip_whitespace();
-        match self.peek()? {
-            '"' => self.parse_string(),
-            '0'..='9' => self.parse_number(),
-            't' | 'f' => self.parse_bool(),
-            '[' => self.parse_array(),
-            'n' => self.parse_null(),
-            '{' => self.parse_object(),
-            _ => None, //FIXME: probably panic or resturn result since all valid json cases have
-                       //already been handled
-        }
-    }
+use json::{JsonValue, SerializeOptions, Validator};
 
-    fn consume(&mut self, to_match: char) -> bool {
-        //TODO: check return type
-        if self.peek() == Some(to_match) {
-            self.advance();
-            true
-        } else {
-            false
-        }
-    }
-
-    // TODO: Return result maybe
-    fn expect(&mut self, to_match: char) {
-        if self.peek() != Some(to_match) {
-            panic!("Expected {}", to_match);
-        }
-        self.advance();
-    }
+const USAGE: &str =
+    "usage: json_parser [--minify | --validate | --query <pointer>] [--ascii] [file]
 
-    fn parse_string(&mut self) -> Option<JsonValue> {
-        if !self.consume('"') {
-            return None;
-        }
-        let mut result = String::new();
-        loop {
-            match self.peek()? {
-                '"' => {
-                    self.advance();
-                    return Some(JsonValue::String(result));
-                }
+Pretty-prints the JSON in <file>, or standard input when no file is given.
+  --minify           print without any whitespace
+  --validate         only check the input, print `valid` if it parses
+  --query <pointer>  print the value at a JSON Pointer such as /users/0/name
+  --ascii            escape every non-ASCII character in the output
 
-                ch => {
-                    result.push(ch);
-                    self.advance();
-                }
-            }
-        }
-    }
+Exits with 1 if the input is invalid or the pointer doesn't resolve, and
+with 2 for bad arguments.";
 
-    fn consume_word(&mut self, word: &str) -> bool {
-        let l = word.len();
-        if self.pos + l <= self.src.len() && (&self.src[self.pos..self.pos + l] == word) {
-            self.pos += l;
-            true
-        } else {
-            false
-        }
-    }
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Pretty,
+    Minify,
+    Validate,
+    Query(String),
+}
 
-    fn parse_null(&mut self) -> Option<JsonValue> {
-        if self.consume_word("null") {
-            Some(JsonValue::Null)
-        } else {
-            None
-        }
-    }
+#[derive(Debug)]
+struct Args {
+    mode: Mode,
+    ascii: bool,
+    file: Option<String>,
+}
 
-    fn parse_number(&mut self) -> Option<JsonValue> {
-        //TODO: add for floats or other number representations
-        let idx = self.pos;
-        loop {
-            match self.peek() {
-                Some(ch) if ch >= '0' && ch <= '9' => {
-                    self.advance();
-                }
-                _ => break,
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut mode = Mode::Pretty;
+    let mut ascii = false;
+    let mut file = None;
+    while let Some(arg) = args.next() {
+        let next_mode = match arg.as_str() {
+            "--minify" => Mode::Minify,
+            "--validate" => Mode::Validate,
+            "--ascii" => {
+                ascii = true;
+                continue;
             }
-        }
-
-        if idx == self.pos {
-            None
-        } else {
-            let value = &self.src[idx..self.pos].parse::<f64>().unwrap();
-            Some(JsonValue::Number(*value))
-        }
-    }
-
-    fn parse_bool(&mut self) -> Option<JsonValue> {
-        if self.consume_word("true") {
-            Some(JsonValue::Bool(true))
-        } else if self.consume_word("false") {
-            Some(JsonValue::Bool(false))
-        } else {
-            None
-        }
-    }
-
-    fn parse_array(&mut self) -> Option<JsonValue> {
-        if self.consume('[') {
-            let mut result = vec![];
-            loop {
-                match self.parse() {
-                    Some(v) => result.push(v),
-                    None => break,
+            "--query" => match args.next() {
+                Some(pointer) if pointer.is_empty() || pointer.starts_with('/') => {
+                    Mode::Query(pointer)
                 }
-
-                self.skip_whitespace();
-
-                if !self.consume(',') {
-                    break;
+                Some(pointer) => {
+                    return Err(format!(
+                        "{:?} is not a JSON Pointer, it must start with /",
+                        pointer
+                    ));
                 }
+                None => return Err("--query needs a pointer".to_string()),
+            },
+            flag if flag.starts_with("--") => return Err(format!("unknown flag {}", flag)),
+            _ if file.is_none() => {
+                file = Some(arg);
+                continue;
             }
-            self.expect(']');
-            Some(JsonValue::Array(result))
-        } else {
-            None
+            _ => return Err(format!("unexpected argument {}", arg)),
+        };
+        if mode != Mode::Pretty {
+            return Err("only one of --minify, --validate and --query can be given".to_string());
         }
+        mode = next_mode;
     }
+    Ok(Args { mode, ascii, file })
+}
 
-    fn parse_object(&mut self) -> Option<JsonValue> {
-        if self.consume('{') {
-            let mut map = HashMap::new();
-            loop {
-                let key = match self.parse() {
-                    Some(JsonValue::String(v)) => v,
-                    //FIXME: move to result types
-                    Some(_) => panic!("Expected a string value as a key"),
-                    None => break,
-                };
-
-                self.skip_whitespace();
-                self.expect(':');
-
-                let value = match self.parse() {
-                    Some(v) => v,
-                    None => panic!("Expected a value for the key {}", key),
-                };
-                map.insert(key, value);
-                self.skip_whitespace();
-                if !self.consume(',') {
-                    break;
+fn read_input(file: Option<&str>) -> Result<JsonValue, json::Error> {
+    match file {
+        Some(path) => json::from_file(path),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            json::from_str(&input).map_err(|error| {
+                let at = error.line_column(&input);
+                json::Error::Line {
+                    line: at.line,
+                    column: at.column,
+                    error,
                 }
-            }
-            self.expect('}');
-            Some(JsonValue::Obj(map))
-        } else {
-            None
+            })
         }
     }
+}
 
-    //TODO: Consider &str instead of String
-    fn peek(&self) -> Option<char> {
-        self.src.chars().nth(self.pos)
-    }
-
-    fn advance(&mut self) -> Option<char> {
-        self.pos += 1;
-        self.src.chars().nth(self.pos - 1)
-    }
-
-    fn skip_whitespace(&mut self) {
-        while let Some(ch) = self.peek() {
-            match ch {
-                '\t' | '\n' | ' ' | '\r' => self.advance(),
-                _ => break,
-            };
+/// Writes the output, treating a closed pipe (e.g. `json_parser | head`) as
+/// the reader having seen enough rather than as a failure.
+fn emit(text: &str) -> ExitCode {
+    match writeln!(io::stdout().lock(), "{}", text) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
         }
+        _ => ExitCode::SUCCESS,
     }
 }
 
-fn main() {
-    let data = fs::read_to_string("todos.json").expect("Failed to read from a file");
-    let mut parser = Parser::new(data);
-    let result = parser.parse().unwrap();
-            println!("{}", result);
+/// Checks the input as it streams in, so any size of file works.
+fn validate(file: Option<&str>) -> ExitCode {
+    let mut validator = Validator::new();
+    let result = match file {
+        Some(path) => match File::open(path) {
+            Ok(file) => validator.check(file),
+            Err(e) => Err(e.into()),
+        },
+        None => validator.check(io::stdin().lock()),
+    };
+    let name = file.unwrap_or("<stdin>");
     match result {
-        JsonValue::Obj(v) => {
-            println!("\n{:?}", &v["total"]);
+        Ok(()) => emit("valid"),
+        Err(json::Error::Parse(e)) => {
+            let at = validator.line_column();
+            eprintln!(
+                "error: {}: {} (line {}, column {})",
+                name, e, at.line, at.column
+            );
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("error: {}: {}", name, e);
+            ExitCode::FAILURE
         }
-        _ => (),
-    };
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_parse_string() {
-        let input = "Testing parse_string()";
-        let mut parser = Parser::new(format!("\"{}\"", input));
-        match parser.parse_string() {
-            Some(JsonValue::String(value)) => assert_eq!(value, input),
-            _ => panic!("Expected String"),
-        };
-    }
-
-    #[test]
-    fn test_skip_whitespaces() {
-        let mut parser = Parser::new("      \t\n ".to_string());
-        parser.skip_whitespace();
-        assert!(matches!(parser.peek(), None));
-
-        let mut parser = Parser::new("      \t\n  a".to_string());
-        parser.skip_whitespace();
-        assert!(matches!(parser.peek(), Some(ch) if ch == 'a'));
-    }
-
-    #[test]
-    fn test_parse_null() {
-        let mut parser = Parser::new("null".to_string());
-        assert!(matches!(parser.parse_null(), Some(JsonValue::Null)));
-
-        let mut parser = Parser::new("nul".to_string());
-        assert!(matches!(parser.parse_null(), None));
     }
+}
 
-    #[test]
-    fn test_parse_bool() {
-        let mut parser = Parser::new("true".to_string());
-        match parser.parse_bool() {
-            Some(JsonValue::Bool(value)) => assert_eq!(value, true),
-            _ => panic!("Expected True"),
-        };
-
-        let mut parser = Parser::new("false".to_string());
-        match parser.parse_bool() {
-            Some(JsonValue::Bool(value)) => assert_eq!(value, false),
-            _ => panic!("Expected False"),
-        };
-
-        let mut parser = Parser::new("fale".to_string());
-        assert!(matches!(parser.parse_bool(), None));
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
     }
+    let args = match parse_args(args.into_iter()) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
 
-    #[test]
-    fn test_parse_number() {
-        let mut parser = Parser::new("01234 abc".to_string());
-        match parser.parse_number() {
-            Some(JsonValue::Number(value)) => assert_eq!(value, 1234_f64),
-            _ => panic!("Expected number: 1234"),
-        };
-
-        let mut parser = Parser::new("false".to_string());
-        assert!(matches!(parser.parse_number(), None));
+    if args.mode == Mode::Validate {
+        return validate(args.file.as_deref());
     }
 
-    #[test]
-    fn test_parse_array() {
-        let mut parser = Parser::new("[1,32,\"abc\", null  ]".to_string());
-        let expected_result = vec![
-            JsonValue::Number(1_f64),
-            JsonValue::Number(32_f64),
-            JsonValue::String("abc".to_string()),
-            JsonValue::Null,
-        ];
-        match parser.parse_array() {
-            Some(JsonValue::Array(value)) => assert_eq!(value, expected_result),
-            _ => panic!("Expected array"),
-        };
-
-        let mut parser = Parser::new("false".to_string());
-        assert!(matches!(parser.parse_array(), None));
-
-        let mut parser = Parser::new("[]".to_string());
-        match parser.parse_array() {
-            Some(JsonValue::Array(value)) => assert_eq!(value, vec![]),
-            _ => panic!("Expected an empty array"),
+    let name = args.file.as_deref().unwrap_or("<stdin>");
+    let value = match read_input(args.file.as_deref()) {
+        Ok(value) => value,
+        Err(json::Error::Line {
+            line,
+            column,
+            error,
+        }) => {
+            eprintln!(
+                "error: {}: {} (line {}, column {})",
+                name, error, line, column
+            );
+            return ExitCode::FAILURE;
         }
-    }
-
-    #[test]
-    fn test_parse_object() {
-        let mut parser = Parser::new(
-            r#"{
-                "one" : 2,
-                "two" : [2, null, false],
-                "three": "third value",
-            }"#
-            .to_string(),
-        );
-
-        let mut expected_result = HashMap::new();
-        expected_result.insert("one".to_string(), JsonValue::Number(2.0));
-        expected_result.insert(
-            "two".to_string(),
-            JsonValue::Array(vec![
-                JsonValue::Number(2.0),
-                JsonValue::Null,
-                JsonValue::Bool(false),
-            ]),
-        );
-        expected_result.insert(
-            "three".to_string(),
-            JsonValue::String("third value".to_string()),
-        );
-
-        match parser.parse_object() {
-            Some(JsonValue::Obj(value)) => assert_eq!(value, expected_result),
-            _ => panic!("Expected array"),
-        };
-
-        let mut parser = Parser::new("false".to_string());
-        assert!(matches!(parser.parse_object(), None));
-
-        let mut parser = Parser::new("{}".to_string());
-        match parser.parse_object() {
-            Some(JsonValue::Obj(value)) => assert_eq!(value, HashMap::new()),
-            _ => panic!("Expected an empty map"),
+        Err(e) => {
+            eprintln!("error: {}: {}", name, e);
+            return ExitCode::FAILURE;
         }
+    };
+
+    let compact = SerializeOptions {
+        escape_non_ascii: args.ascii,
+        ..Default::default()
+    };
+    let pretty = SerializeOptions {
+        indent: Some(2),
+        ..compact.clone()
+    };
+    match args.mode {
+        Mode::Pretty => emit(&value.to_string_with_options(&pretty)),
+        Mode::Minify => emit(&value.to_string_with_options(&compact)),
+        Mode::Validate => unreachable!(),
+        Mode::Query(pointer) => match value.pointer(&pointer) {
+            Some(found) => emit(&found.to_string_with_options(&pretty)),
+            None => {
+                eprintln!("error: nothing at {}", pointer);
+                ExitCode::FAILURE
+            }
+        },
     }
 }
@@ -0,0 +1,489 @@
+use std::ops::{BitOr, BitOrAssign};
+
+/// Set of comment syntaxes the tokenizer skips over like whitespace.
+/// Combine them with `|`, e.g. `CommentStyle::LINE | CommentStyle::BLOCK`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CommentStyle(u8);
+
+impl CommentStyle {
+    pub const NONE: CommentStyle = CommentStyle(0);
+    /// `// until the end of the line`
+    pub const LINE: CommentStyle = CommentStyle(1);
+    /// `/* until the closing star-slash */`
+    pub const BLOCK: CommentStyle = CommentStyle(1 << 1);
+    /// `# until the end of the line`
+    pub const HASH: CommentStyle = CommentStyle(1 << 2);
+    pub const ALL: CommentStyle = CommentStyle(0b111);
+
+    pub fn contains(self, other: CommentStyle) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for CommentStyle {
+    type Output = CommentStyle;
+
+    fn bitor(self, rhs: CommentStyle) -> CommentStyle {
+        CommentStyle(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for CommentStyle {
+    fn bitor_assign(&mut self, rhs: CommentStyle) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// What to do when an object repeats a key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// The last value wins, in the position of the first occurrence.
+    #[default]
+    KeepLast,
+    KeepFirst,
+    /// Fail with `ParseError::DuplicateKey`.
+    Error,
+    /// Collect every value of a repeated key into an array, in the position
+    /// of the first occurrence: `{"a": 1, "a": 2}` is `{"a": [1, 2]}`.
+    Merge,
+}
+
+/// What to make of an integer literal beyond ±(2^53 - 1), where an `f64`
+/// can no longer hold every integer exactly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BigIntegers {
+    /// A `Number`, rounded to the nearest `f64`.
+    #[default]
+    Float,
+    /// A `String` of the digits.
+    String,
+    /// An `Int` if it fits an `i64`, a `RawNumber` of the digits otherwise.
+    Exact,
+}
+
+/// Knobs that change how `Parser` reads its input.
+///
+/// The default accepts plain JSON plus trailing commas, leading zeros and
+/// raw control characters in strings. `strict()` and `lenient()` are
+/// presets for the two ends of the spectrum; neither sets the depth or size
+/// limits, those depend on how much the input is trusted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserOptions {
+    /// Round every parsed number to this many significant digits.
+    pub number_precision: Option<u32>,
+    /// Fail with `NumberOutOfRange` on literals like `1e400` that overflow
+    /// an `f64`, instead of reading them as infinity.
+    pub reject_out_of_range: bool,
+    /// How to keep integers too large for an `f64` to hold exactly.
+    pub big_integers: BigIntegers,
+    /// Scan ahead to count the elements of each array/object and allocate it
+    /// once at the right size, instead of growing it. Each container's text is
+    /// scanned once for itself and again for every container around it, so
+    /// the cost grows with the size of the text times its nesting depth.
+    pub presize_containers: bool,
+    /// Comment syntaxes to accept, none by default.
+    pub comments: CommentStyle,
+    /// Accept a comma before the closing `]` or `}`.
+    pub trailing_commas: bool,
+    /// Accept strings delimited by `'` as well as `"`.
+    pub single_quotes: bool,
+    /// Accept the `NaN`, `Infinity` and `-Infinity` literals.
+    pub nan_infinity: bool,
+    /// Accept a `+` sign in front of numbers, as JSON5 does.
+    pub leading_plus: bool,
+    /// Accept integer parts with leading zeros, reading `007` as 7.
+    pub leading_zeros: bool,
+    /// Accept the control characters U+0001 to U+001F written raw inside
+    /// strings, not only escaped. A raw NUL is rejected either way.
+    pub control_characters: bool,
+    /// Report `+5` and `.5`, common slips when writing JSON by hand, with
+    /// their own errors saying how to fix them, rather than as an unexpected
+    /// character. Only changes the error; they are rejected either way. On in
+    /// `strict()`.
+    pub number_hints: bool,
+    /// Skip a first line starting with `#!`, as in executable config files.
+    pub shebang: bool,
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// Object keys, at any depth, whose values are kept as `RawJson` text
+    /// instead of being turned into a tree. The text is checked under these
+    /// same options, so comments, trailing commas and the like stay in it and
+    /// are written back as they are by `to_string`.
+    pub raw_keys: Vec<String>,
+    /// Maximum nesting of arrays and objects.
+    pub max_depth: Option<usize>,
+    /// Maximum number of elements in any one array.
+    pub max_array_elements: Option<usize>,
+    /// Maximum number of entries in any one object, counted as written so
+    /// repeated keys count each time.
+    pub max_object_entries: Option<usize>,
+    /// Maximum number of input bytes to read.
+    pub max_size: Option<usize>,
+    /// Maximum amount of work, counted as input bytes read plus values
+    /// built. Bounds the cost of untrusted input without a clock.
+    pub max_steps: Option<usize>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            number_precision: None,
+            reject_out_of_range: false,
+            big_integers: BigIntegers::Float,
+            presize_containers: false,
+            comments: CommentStyle::NONE,
+            trailing_commas: true,
+            single_quotes: false,
+            nan_infinity: false,
+            leading_plus: false,
+            leading_zeros: true,
+            control_characters: true,
+            number_hints: false,
+            shebang: false,
+            duplicate_keys: DuplicateKeyPolicy::KeepLast,
+            raw_keys: vec![],
+            max_depth: None,
+            max_array_elements: None,
+            max_object_entries: None,
+            max_size: None,
+            max_steps: None,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Only what RFC 8259 allows, and duplicate keys and numbers out of
+    /// range are errors.
+    pub fn strict() -> Self {
+        ParserOptions {
+            trailing_commas: false,
+            leading_zeros: false,
+            control_characters: false,
+            number_hints: true,
+            reject_out_of_range: true,
+            duplicate_keys: DuplicateKeyPolicy::Error,
+            ..Default::default()
+        }
+    }
+
+    /// Accepts every extension the parser knows about.
+    pub fn lenient() -> Self {
+        ParserOptions {
+            comments: CommentStyle::ALL,
+            trailing_commas: true,
+            single_quotes: true,
+            nan_infinity: true,
+            leading_plus: true,
+            shebang: true,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JsonValue, ParseError, from_str_with_options};
+
+    #[test]
+    fn test_strict_rejects_what_lenient_accepts() {
+        let cases = [
+            "[1, 2,]",
+            r#"{"a": 1,}"#,
+            "[1 // one\n]",
+            "['single']",
+            "[NaN, Infinity, -Infinity]",
+            "+5",
+            r#"{"a": 1, "a": 2}"#,
+            "1e400",
+            "007",
+            "[-01.5]",
+            "\"tab\there\"",
+            "\"bell\u{7}\"",
+        ];
+        for input in cases {
+            assert!(
+                from_str_with_options(input, ParserOptions::strict()).is_err(),
+                "{}",
+                input
+            );
+            assert!(
+                from_str_with_options(input, ParserOptions::lenient()).is_ok(),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_strict_numbers_and_control_characters() {
+        let strict = |input| from_str_with_options(input, ParserOptions::strict());
+        assert_eq!(
+            strict("[1, 007]"),
+            Err(ParseError::UnexpectedChar { ch: '0', pos: 5 })
+        );
+        assert_eq!(
+            strict("\"a\nb\""),
+            Err(ParseError::UnexpectedChar { ch: '\n', pos: 2 })
+        );
+        assert_eq!(
+            strict(r#"[0, -0, 0.05, 10, 0e1, "a\tb\u0001"]"#)
+                .unwrap()
+                .to_string(),
+            r#"[0,-0,0.05,10,0,"a\tb\u0001"]"#
+        );
+        assert_eq!(
+            from_str_with_options("\"a\nb\"", ParserOptions::default()),
+            Ok(JsonValue::String("a\nb".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_lenient_values() {
+        let value = from_str_with_options(r"['it\'s', -Infinity]", ParserOptions::lenient());
+        assert_eq!(
+            value,
+            Ok(JsonValue::Array(vec![
+                JsonValue::String("it's".to_string()),
+                JsonValue::Number(f64::NEG_INFINITY),
+            ]))
+        );
+        assert_eq!(value.unwrap().to_string(), r#"["it's",-Infinity]"#);
+    }
+
+    #[test]
+    fn test_reject_out_of_range() {
+        assert_eq!(
+            from_str_with_options("[1, -1e400]", ParserOptions::strict()),
+            Err(ParseError::NumberOutOfRange { pos: 4 })
+        );
+        assert_eq!(
+            from_str_with_options("1e400", ParserOptions::strict()),
+            Err(ParseError::NumberOutOfRange { pos: 0 })
+        );
+        assert_eq!(
+            from_str_with_options("[1e308, 1e-400]", ParserOptions::strict()),
+            Ok(JsonValue::Array(vec![
+                JsonValue::Number(1e308),
+                JsonValue::Number(0.0)
+            ]))
+        );
+        assert_eq!(
+            from_str_with_options("1e400", ParserOptions::default()),
+            Ok(JsonValue::Number(f64::INFINITY))
+        );
+    }
+
+    #[test]
+    fn test_comma_is_never_a_decimal_separator() {
+        let two = JsonValue::Array(vec![JsonValue::Number(1.0), JsonValue::Number(5.0)]);
+        for options in [
+            ParserOptions::default(),
+            ParserOptions::strict(),
+            ParserOptions::lenient(),
+        ] {
+            assert_eq!(
+                from_str_with_options("[1,5]", options.clone()),
+                Ok(two.clone())
+            );
+            assert!(from_str_with_options("1,5", options).is_err());
+        }
+    }
+
+    #[test]
+    fn test_leading_plus() {
+        let parse = |input| from_str_with_options(input, ParserOptions::lenient());
+        assert_eq!(parse("+5"), Ok(JsonValue::Number(5.0)));
+        assert_eq!(
+            parse("[+1.5e2, +Infinity]").unwrap().to_string(),
+            "[150,Infinity]"
+        );
+        assert_eq!(
+            parse("+-5"),
+            Err(ParseError::UnexpectedChar { ch: '-', pos: 1 })
+        );
+        assert_eq!(
+            from_str_with_options("+5", ParserOptions::strict()),
+            Err(ParseError::LeadingPlus { pos: 0 })
+        );
+        assert_eq!(
+            from_str_with_options("+5", ParserOptions::default()),
+            Err(ParseError::UnexpectedChar { ch: '+', pos: 0 })
+        );
+    }
+
+    #[test]
+    fn test_number_hints() {
+        let parse = |input| from_str_with_options(input, ParserOptions::strict());
+        let error = parse(".5").unwrap_err();
+        assert_eq!(
+            error,
+            ParseError::MissingLeadingZero {
+                fixed: "0.5".to_string(),
+                pos: 0
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "number starting with '.' at position 0, JSON needs a digit first: write 0.5 instead"
+        );
+        let error = parse("[1, +5]").unwrap_err();
+        assert_eq!(error, ParseError::LeadingPlus { pos: 4 });
+        assert_eq!(
+            error.to_string(),
+            "number with a leading '+' at position 4, JSON doesn't allow it: remove the '+'"
+        );
+        assert_eq!(
+            parse(".25e3").unwrap_err(),
+            ParseError::MissingLeadingZero {
+                fixed: "0.25e3".to_string(),
+                pos: 0
+            }
+        );
+        assert_eq!(
+            parse("[+]"),
+            Err(ParseError::UnexpectedChar { ch: '+', pos: 1 })
+        );
+        // No hint when following it would still not be a number
+        for (input, ch) in [(".5e", '.'), (".5.5", '.'), ("+.5", '+'), ("+007", '+')] {
+            assert_eq!(
+                parse(input),
+                Err(ParseError::UnexpectedChar { ch, pos: 0 }),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            from_str_with_options(".5", ParserOptions::default()),
+            Err(ParseError::UnexpectedChar { ch: '.', pos: 0 })
+        );
+    }
+
+    #[test]
+    fn test_shebang() {
+        let input = "#!/usr/bin/env run-config\n{\"a\": 1}\n";
+        let expected = from_str_with_options(r#"{"a": 1}"#, ParserOptions::default());
+        assert_eq!(
+            from_str_with_options(input, ParserOptions::lenient()),
+            expected
+        );
+        let options = ParserOptions {
+            shebang: true,
+            ..Default::default()
+        };
+        assert_eq!(from_str_with_options(input, options.clone()), expected);
+        assert_eq!(
+            from_str_with_options(input, ParserOptions::strict()),
+            Err(ParseError::UnexpectedChar { ch: '#', pos: 0 })
+        );
+        // Only on the first line
+        assert!(from_str_with_options("\n#!x\n1", options).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let input = r#"{"a": 1, "b": 2, "a": 3}"#;
+        let parse = |duplicate_keys| {
+            let options = ParserOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            from_str_with_options(input, options).map(|v| v.get("a").cloned())
+        };
+        assert_eq!(
+            parse(DuplicateKeyPolicy::KeepLast),
+            Ok(Some(JsonValue::Number(3.0)))
+        );
+        assert_eq!(
+            parse(DuplicateKeyPolicy::KeepFirst),
+            Ok(Some(JsonValue::Number(1.0)))
+        );
+        assert_eq!(
+            parse(DuplicateKeyPolicy::Error),
+            Err(ParseError::DuplicateKey {
+                key: "a".to_string(),
+                pos: 17
+            })
+        );
+        assert_eq!(
+            parse(DuplicateKeyPolicy::Merge),
+            Ok(Some(
+                from_str_with_options("[1, 3]", ParserOptions::default()).unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_merge_duplicate_keys() {
+        let options = ParserOptions {
+            duplicate_keys: DuplicateKeyPolicy::Merge,
+            ..Default::default()
+        };
+        let parse = |input| from_str_with_options(input, options.clone()).unwrap();
+        assert_eq!(parse(r#"{"a":1,"a":2}"#), parse(r#"{"a":[1,2]}"#));
+        // An array value is only an element, the third occurrence included
+        assert_eq!(
+            parse(r#"{"a": [1], "b": {"c": 0, "c": 0}, "a": [2], "a": 3}"#),
+            parse(r#"{"a": [[1], [2], 3], "b": {"c": [0, 0]}}"#)
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let options = ParserOptions {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        assert!(from_str_with_options("[[1]]", options.clone()).is_ok());
+        assert_eq!(
+            from_str_with_options("[[[1]]]", options),
+            Err(ParseError::DepthLimitExceeded { pos: 2 })
+        );
+
+        let options = ParserOptions {
+            max_array_elements: Some(2),
+            max_object_entries: Some(2),
+            ..Default::default()
+        };
+        assert!(
+            from_str_with_options(r#"[[1, 2], {"a": 1, "b": [3, 4]}]"#, options.clone()).is_ok()
+        );
+        assert!(from_str_with_options("[[], {}]", options.clone()).is_ok());
+        assert_eq!(
+            from_str_with_options("[[1, 2, 3]]", options.clone()),
+            Err(ParseError::TooManyElements { limit: 2, pos: 8 })
+        );
+        assert_eq!(
+            from_str_with_options(r#"{"a": 1, "b": 2, "a": 3}"#, options.clone()),
+            Err(ParseError::TooManyElements { limit: 2, pos: 17 })
+        );
+        assert_eq!(
+            from_str_with_options("[1, 2, {}]", options),
+            Err(ParseError::TooManyElements { limit: 2, pos: 7 })
+        );
+
+        let options = ParserOptions {
+            max_size: Some(8),
+            ..Default::default()
+        };
+        assert!(from_str_with_options("[1, 2]", options.clone()).is_ok());
+        assert_eq!(
+            from_str_with_options("[1, 2, 3, 4]", options),
+            Err(ParseError::SizeLimitExceeded { limit: 8 })
+        );
+
+        let options = ParserOptions {
+            max_steps: Some(50),
+            ..Default::default()
+        };
+        assert!(from_str_with_options("[1, 2, 3]", options.clone()).is_ok());
+        let input = format!("[{}]", vec!["[1, 2]"; 20].join(","));
+        assert_eq!(
+            from_str_with_options(&input, options),
+            Err(ParseError::StepLimitExceeded { limit: 50 })
+        );
+    }
+}
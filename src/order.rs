@@ -0,0 +1,300 @@
+use std::cmp::Ordering;
+
+use crate::JsonValue;
+
+/// A total order over JSON values, for sorting mixed arrays the same way
+/// every time.
+///
+/// Values of different types order as null < booleans < numbers < strings <
+/// arrays < objects < raw JSON. Within a type:
+/// - `false` before `true`
+/// - numbers by value, exactly even between `Int`, `RawNumber` and `Number`.
+///   `-0` equals `0`. As with `f64::total_cmp`, `NaN` sorts after every
+///   other number and `-NaN` before. A `RawNumber` that isn't a number
+///   sorts last, by its text
+/// - strings, and raw JSON text, by their UTF-8 bytes
+/// - arrays element by element, a prefix first
+/// - objects by their entries sorted by key, so insertion order doesn't
+///   matter, just as for `==`
+pub fn cmp_json(a: &JsonValue, b: &JsonValue) -> Ordering {
+    match (a, b) {
+        (JsonValue::Bool(a), JsonValue::Bool(b)) => a.cmp(b),
+        (JsonValue::Number(a), JsonValue::Number(b)) => {
+            without_sign_of_zero(*a).total_cmp(&without_sign_of_zero(*b))
+        }
+        (JsonValue::Int(a), JsonValue::Int(b)) => a.cmp(b),
+        (a, b) if rank(a) == NUMBER && rank(b) == NUMBER => {
+            NumberKey::of(a).cmp_exact(&NumberKey::of(b))
+        }
+        (JsonValue::String(a), JsonValue::String(b))
+        | (JsonValue::RawJson(a), JsonValue::RawJson(b)) => a.cmp(b),
+        (JsonValue::Array(a), JsonValue::Array(b)) => first_difference(
+            a.iter().zip(b).map(|(x, y)| cmp_json(x, y)),
+            a.len().cmp(&b.len()),
+        ),
+        (JsonValue::Obj(a), JsonValue::Obj(b)) => {
+            let mut a: Vec<_> = a.iter().collect();
+            let mut b: Vec<_> = b.iter().collect();
+            a.sort_by(|x, y| x.0.cmp(y.0));
+            b.sort_by(|x, y| x.0.cmp(y.0));
+            first_difference(
+                a.iter()
+                    .zip(&b)
+                    .map(|((ka, va), (kb, vb))| ka.cmp(kb).then_with(|| cmp_json(va, vb))),
+                a.len().cmp(&b.len()),
+            )
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+const NUMBER: u8 = 2;
+
+fn rank(value: &JsonValue) -> u8 {
+    match value {
+        JsonValue::Null => 0,
+        JsonValue::Bool(_) => 1,
+        JsonValue::Number(_) | JsonValue::Int(_) | JsonValue::RawNumber(_) => NUMBER,
+        JsonValue::String(_) => 3,
+        JsonValue::Array(_) => 4,
+        JsonValue::Obj(_) => 5,
+        JsonValue::RawJson(_) => 6,
+    }
+}
+
+/// A number of any variant, in a form two of them can be compared by
+/// exactly. Only used across variants, `Number` and `Int` pairs have their
+/// own faster comparisons.
+enum NumberKey<'a> {
+    NegativeNaN,
+    NegativeInfinity,
+    /// `integer + fraction`, with the integer part written in decimal as a
+    /// sign and digits without leading zeros, and `fraction` in (-1, 1) of
+    /// the same sign.
+    Finite {
+        negative: bool,
+        digits: String,
+        fraction: f64,
+    },
+    Infinity,
+    NaN,
+    /// A `RawNumber` holding something other than a number.
+    Invalid(&'a str),
+}
+
+impl NumberKey<'_> {
+    fn of(number: &JsonValue) -> NumberKey<'_> {
+        match number {
+            JsonValue::Number(n) => NumberKey::from_f64(*n),
+            JsonValue::Int(i) => NumberKey::Finite {
+                negative: *i < 0,
+                digits: i.unsigned_abs().to_string(),
+                fraction: 0.0,
+            },
+            JsonValue::RawNumber(raw) => {
+                let (negative, digits) = match raw.strip_prefix('-') {
+                    Some(digits) => (true, digits),
+                    None => (false, raw.as_str()),
+                };
+                if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+                    let digits = digits.trim_start_matches('0');
+                    NumberKey::Finite {
+                        negative: negative && !digits.is_empty(),
+                        digits: if digits.is_empty() { "0" } else { digits }.to_string(),
+                        fraction: 0.0,
+                    }
+                } else {
+                    raw.parse()
+                        .map_or(NumberKey::Invalid(raw), NumberKey::from_f64)
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn from_f64<'a>(n: f64) -> NumberKey<'a> {
+        if n.is_nan() {
+            if n.is_sign_negative() {
+                NumberKey::NegativeNaN
+            } else {
+                NumberKey::NaN
+            }
+        } else if n.is_infinite() {
+            if n < 0.0 {
+                NumberKey::NegativeInfinity
+            } else {
+                NumberKey::Infinity
+            }
+        } else {
+            let integer = n.trunc();
+            NumberKey::Finite {
+                negative: integer < 0.0,
+                // Exact: `{:.0}` writes every digit of a whole `f64`
+                digits: format!("{:.0}", integer.abs()),
+                fraction: without_sign_of_zero(n.fract()),
+            }
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            NumberKey::NegativeNaN => 0,
+            NumberKey::NegativeInfinity => 1,
+            NumberKey::Finite { .. } => 2,
+            NumberKey::Infinity => 3,
+            NumberKey::NaN => 4,
+            NumberKey::Invalid(_) => 5,
+        }
+    }
+
+    fn cmp_exact(&self, other: &NumberKey) -> Ordering {
+        match (self, other) {
+            (
+                NumberKey::Finite {
+                    negative: a_negative,
+                    digits: a_digits,
+                    fraction: a_fraction,
+                },
+                NumberKey::Finite {
+                    negative: b_negative,
+                    digits: b_digits,
+                    fraction: b_fraction,
+                },
+            ) => {
+                let magnitude = (a_digits.len(), a_digits).cmp(&(b_digits.len(), b_digits));
+                let integer = match (a_negative, b_negative) {
+                    (false, false) => magnitude,
+                    (true, true) => magnitude.reverse(),
+                    _ => b_negative.cmp(a_negative),
+                };
+                integer.then_with(|| a_fraction.total_cmp(b_fraction))
+            }
+            (NumberKey::Invalid(a), NumberKey::Invalid(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+/// Lexicographic comparison: the first unequal pair decides, then the length.
+fn first_difference(mut pairs: impl Iterator<Item = Ordering>, by_len: Ordering) -> Ordering {
+    pairs.find(|ord| ord.is_ne()).unwrap_or(by_len)
+}
+
+fn without_sign_of_zero(n: f64) -> f64 {
+    if n == 0.0 { 0.0 } else { n }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_sort_mixed() {
+        let JsonValue::Array(mut values) = from_str(
+            r#"[{"b": 1, "a": 2}, "b", 2, [1, 2], null, true, {"a": 2}, -0, "a", [1], false, 0, [], {}]"#,
+        )
+        .unwrap() else {
+            unreachable!()
+        };
+        values.push(JsonValue::Number(f64::NAN));
+        values.push(JsonValue::Number(f64::NEG_INFINITY));
+        values.sort_by(cmp_json);
+
+        let sorted: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            sorted,
+            [
+                "null",
+                "false",
+                "true",
+                "-Infinity",
+                "-0",
+                "0",
+                "2",
+                "NaN",
+                "\"a\"",
+                "\"b\"",
+                "[]",
+                "[1]",
+                "[1,2]",
+                "{}",
+                "{\"a\":2}",
+                "{\"b\":1,\"a\":2}",
+            ]
+        );
+
+        assert_eq!(
+            cmp_json(&JsonValue::Int(2), &JsonValue::Number(2.5)),
+            Ordering::Less
+        );
+        assert_eq!(
+            cmp_json(&JsonValue::Number(-0.0), &JsonValue::Int(0)),
+            Ordering::Equal
+        );
+
+        let a = from_str(r#"{"x": 1, "y": [null]}"#).unwrap();
+        let b = from_str(r#"{"y": [null], "x": 1}"#).unwrap();
+        assert_eq!(cmp_json(&a, &b), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_numbers_compare_exactly() {
+        let big = 1i64 << 53;
+        assert_eq!(
+            cmp_json(&JsonValue::Int(big), &JsonValue::Number(big as f64)),
+            Ordering::Equal
+        );
+        assert_eq!(
+            cmp_json(&JsonValue::Number(big as f64), &JsonValue::Int(big + 1)),
+            Ordering::Less
+        );
+        assert_eq!(
+            cmp_json(&JsonValue::Int(big), &JsonValue::Int(big + 1)),
+            Ordering::Less
+        );
+
+        let raw = |text: &str| JsonValue::RawNumber(text.to_string());
+        let values = [
+            JsonValue::Number(-f64::NAN),
+            JsonValue::Number(f64::NEG_INFINITY),
+            raw("-18446744073709551617"),
+            JsonValue::Number(-18446744073709551616.0),
+            JsonValue::Int(i64::MIN),
+            JsonValue::Number(-1.5),
+            JsonValue::Int(-1),
+            JsonValue::Number(-0.5),
+            JsonValue::Int(0),
+            JsonValue::Number(0.5),
+            JsonValue::Int(1),
+            JsonValue::Number(1.5),
+            JsonValue::Int(big),
+            JsonValue::Int(big + 1),
+            JsonValue::Number((big + 2) as f64),
+            JsonValue::Int(i64::MAX),
+            JsonValue::Number(9223372036854775808.0),
+            raw("18446744073709551616"),
+            raw("18446744073709551617"),
+            JsonValue::Number(1e300),
+            JsonValue::Number(f64::INFINITY),
+            JsonValue::Number(f64::NAN),
+            raw("not a number"),
+        ];
+        for (i, a) in values.iter().enumerate() {
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(cmp_json(a, b), i.cmp(&j), "{} vs {}", a, b);
+            }
+        }
+        assert_eq!(
+            cmp_json(
+                &JsonValue::Number(9223372036854775808.0),
+                &raw("9223372036854775808")
+            ),
+            Ordering::Equal
+        );
+        assert_eq!(
+            cmp_json(&raw("-0"), &JsonValue::Number(0.0)),
+            Ordering::Equal
+        );
+    }
+}
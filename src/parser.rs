@@ -0,0 +1,970 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::tokenizer::{Span, Token, Tokenizer};
+use crate::{BigIntegers, DuplicateKeyPolicy, JsonValue, Map, ParseError, ParserOptions};
+
+/// 2^53 - 1: up to here, every integer has an exact `f64`.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+#[derive(Debug)]
+pub struct Parser {
+    tokens: Tokenizer,
+    peeked: Option<(Token, Span)>,
+    options: ParserOptions,
+    progress: Option<Progress>,
+    depth: usize,
+    /// Values built so far, for `max_steps`.
+    nodes: usize,
+}
+
+struct Progress {
+    interval: usize,
+    next: usize,
+    callback: Box<dyn FnMut(usize) -> bool>,
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("interval", &self.interval)
+            .field("next", &self.next)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Parser {
+    pub fn new(src: String) -> Parser {
+        Parser::with_options(src, ParserOptions::default())
+    }
+
+    pub fn with_options(src: String, options: ParserOptions) -> Parser {
+        Parser {
+            tokens: Tokenizer::with_options(src, options.clone()),
+            peeked: None,
+            options,
+            progress: None,
+            depth: 0,
+            nodes: 0,
+        }
+    }
+
+    /// Calls `callback` with the current byte offset every time another
+    /// `interval` bytes have been read. Returning `false` cancels the parse,
+    /// which then fails with `ParseError::Cancelled`.
+    pub fn on_progress(&mut self, interval: usize, callback: impl FnMut(usize) -> bool + 'static) {
+        let interval = interval.max(1);
+        self.progress = Some(Progress {
+            interval,
+            next: self.tokens.position() + interval,
+            callback: Box::new(callback),
+        });
+    }
+
+    /// Byte offset of the first input not yet used by a parsed value.
+    pub fn position(&self) -> usize {
+        match &self.peeked {
+            Some((_, span)) => span.start,
+            None => self.tokens.position(),
+        }
+    }
+
+    /// The input after `position`.
+    pub fn remaining(&self) -> &str {
+        &self.tokens.source()[self.position()..]
+    }
+
+    /// Parses the next value and stops right after it, leaving whatever
+    /// follows for the caller. `from_str` is this plus a check that nothing
+    /// but whitespace is left.
+    pub fn parse_value(&mut self) -> Result<JsonValue, ParseError> {
+        self.nodes += 1;
+        self.check_steps()?;
+        match self.peek()? {
+            Some((Token::String(_), _)) => self.parse_string(),
+            Some((Token::Number(_), _)) => self.parse_number(),
+            Some((Token::True | Token::False, _)) => self.parse_bool(),
+            Some((Token::LBracket, _)) => self.parse_array(),
+            Some((Token::Null, _)) => self.parse_null(),
+            Some((Token::LBrace, _)) => self.parse_object(),
+            Some(&(_, span)) => Err(self.unexpected(span)),
+            None => Err(ParseError::UnexpectedEof),
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<&(Token, Span)>, ParseError> {
+        if self.peeked.is_none() {
+            self.peeked = self.read_token()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next(&mut self) -> Result<(Token, Span), ParseError> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self.read_token()?.ok_or(ParseError::UnexpectedEof),
+        }
+    }
+
+    /// Pulls the next token from the tokenizer, reporting progress on the way.
+    fn read_token(&mut self) -> Result<Option<(Token, Span)>, ParseError> {
+        let token = self.tokens.next_token()?;
+        if let Some(limit) = self.options.max_size
+            && self.tokens.position() > limit
+        {
+            return Err(ParseError::SizeLimitExceeded { limit });
+        }
+        self.check_steps()?;
+        if let Some(progress) = &mut self.progress {
+            let pos = self.tokens.position();
+            if pos >= progress.next {
+                if !(progress.callback)(pos) {
+                    return Err(ParseError::Cancelled { pos });
+                }
+                progress.next = pos + progress.interval;
+            }
+        }
+        Ok(token)
+    }
+
+    fn consume(&mut self, to_match: &Token) -> Result<bool, ParseError> {
+        if matches!(self.peek()?, Some((token, _)) if token == to_match) {
+            self.peeked = None;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, to_match: Token) -> Result<(), ParseError> {
+        let (token, span) = self.next()?;
+        if token == to_match {
+            return Ok(());
+        }
+        match to_match.delimiter() {
+            Some(expected) => Err(self.expected(expected, span)),
+            None => Err(self.unexpected(span)),
+        }
+    }
+
+    /// Error for a token that can't appear where it was found.
+    fn unexpected(&self, span: Span) -> ParseError {
+        match self.tokens.source()[span.start..].chars().next() {
+            Some(ch) => ParseError::UnexpectedChar {
+                ch,
+                pos: span.start,
+            },
+            None => ParseError::UnexpectedEof,
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<JsonValue, ParseError> {
+        match self.next()? {
+            (Token::String(s), _) => Ok(JsonValue::String(s)),
+            (_, span) => Err(self.unexpected(span)),
+        }
+    }
+
+    fn parse_null(&mut self) -> Result<JsonValue, ParseError> {
+        match self.next()? {
+            (Token::Null, _) => Ok(JsonValue::Null),
+            (_, span) => Err(self.unexpected(span)),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, ParseError> {
+        let mut value = match self.next()? {
+            (Token::Number(n), span) => {
+                let text = &self.tokens.source()[span.start..span.end];
+                if self.options.big_integers != BigIntegers::Float
+                    && n.abs() > MAX_SAFE_INTEGER
+                    && text
+                        .bytes()
+                        .all(|b| b.is_ascii_digit() || b == b'-' || b == b'+')
+                {
+                    let digits = text.strip_prefix('+').unwrap_or(text);
+                    return Ok(match self.options.big_integers {
+                        BigIntegers::Exact => match digits.parse() {
+                            Ok(i) => JsonValue::Int(i),
+                            Err(_) => JsonValue::RawNumber(digits.to_string()),
+                        },
+                        _ => JsonValue::String(digits.to_string()),
+                    });
+                }
+                n
+            }
+            (_, span) => return Err(self.unexpected(span)),
+        };
+        if let Some(precision) = self.options.number_precision {
+            value = round_to_precision(value, precision);
+        }
+        Ok(JsonValue::Number(value))
+    }
+
+    fn parse_bool(&mut self) -> Result<JsonValue, ParseError> {
+        match self.next()? {
+            (Token::True, _) => Ok(JsonValue::Bool(true)),
+            (Token::False, _) => Ok(JsonValue::Bool(false)),
+            (_, span) => Err(self.unexpected(span)),
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, ParseError> {
+        match self.next()? {
+            (Token::LBracket, span) => self.enter(span)?,
+            (_, span) => return Err(self.unexpected(span)),
+        }
+        let mut result = if self.options.presize_containers {
+            Vec::with_capacity(self.tokens.count_elements())
+        } else {
+            vec![]
+        };
+        loop {
+            // Covers both the empty array and a trailing comma
+            if self.consume(&Token::RBracket)? {
+                break;
+            }
+            self.check_count(result.len(), self.options.max_array_elements)?;
+            result.push(self.parse_value()?);
+
+            if !self.consume(&Token::Comma)? {
+                self.expect(Token::RBracket)?;
+                break;
+            }
+            self.check_trailing_comma(&Token::RBracket)?;
+        }
+        self.depth -= 1;
+        Ok(JsonValue::Array(result))
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, ParseError> {
+        match self.next()? {
+            (Token::LBrace, span) => self.enter(span)?,
+            (_, span) => return Err(self.unexpected(span)),
+        }
+        let mut map = if self.options.presize_containers {
+            Map::with_capacity(self.tokens.count_elements())
+        } else {
+            Map::new()
+        };
+        // Keys already turned into an array by `DuplicateKeyPolicy::Merge`
+        let mut merged = HashSet::new();
+        let mut entries = 0;
+        loop {
+            if self.consume(&Token::RBrace)? {
+                break;
+            }
+            self.check_count(entries, self.options.max_object_entries)?;
+            entries += 1;
+            let (key, key_span) = match self.next()? {
+                (Token::String(v), span) => (v, span),
+                (_, span) => return Err(ParseError::ExpectedKey { pos: span.start }),
+            };
+
+            self.expect(Token::Colon)?;
+
+            let value = if self.options.raw_keys.contains(&key) {
+                self.parse_raw()?
+            } else {
+                self.parse_value()?
+            };
+            match self.options.duplicate_keys {
+                DuplicateKeyPolicy::KeepLast => {
+                    map.insert(key, value);
+                }
+                DuplicateKeyPolicy::KeepFirst => {
+                    map.entry(key).or_insert(value);
+                }
+                DuplicateKeyPolicy::Error if map.contains_key(&key) => {
+                    return Err(ParseError::DuplicateKey {
+                        key,
+                        pos: key_span.start,
+                    });
+                }
+                DuplicateKeyPolicy::Error => {
+                    map.insert(key, value);
+                }
+                DuplicateKeyPolicy::Merge => match map.get_mut(&key) {
+                    Some(JsonValue::Array(values)) if merged.contains(&key) => values.push(value),
+                    Some(first) => {
+                        *first = JsonValue::Array(vec![std::mem::take(first), value]);
+                        merged.insert(key);
+                    }
+                    None => {
+                        map.insert(key, value);
+                    }
+                },
+            }
+            if !self.consume(&Token::Comma)? {
+                self.expect(Token::RBrace)?;
+                break;
+            }
+            self.check_trailing_comma(&Token::RBrace)?;
+        }
+        self.depth -= 1;
+        Ok(JsonValue::Obj(map))
+    }
+
+    /// Parses the next value only to find where it ends, and keeps its text.
+    fn parse_raw(&mut self) -> Result<JsonValue, ParseError> {
+        let start = match self.peek()? {
+            Some(&(_, span)) => span.start,
+            None => return Err(ParseError::UnexpectedEof),
+        };
+        self.parse_value()?;
+        let raw = &self.tokens.source()[start..self.tokens.position()];
+        Ok(JsonValue::RawJson(raw.to_string()))
+    }
+
+    fn check_steps(&self) -> Result<(), ParseError> {
+        match self.options.max_steps {
+            Some(limit) if self.tokens.position() + self.nodes > limit => {
+                Err(ParseError::StepLimitExceeded { limit })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Steps into a container, enforcing `max_depth`.
+    fn enter(&mut self, span: Span) -> Result<(), ParseError> {
+        self.depth += 1;
+        match self.options.max_depth {
+            Some(max) if self.depth > max => {
+                Err(ParseError::DepthLimitExceeded { pos: span.start })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Called before reading element number `count + 1` of a container.
+    fn check_count(&self, count: usize, limit: Option<usize>) -> Result<(), ParseError> {
+        match limit {
+            Some(limit) if count >= limit => Err(ParseError::TooManyElements {
+                limit,
+                pos: self.position(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Called after a comma, rejects `close` right after it unless trailing
+    /// commas are allowed.
+    fn check_trailing_comma(&mut self, close: &Token) -> Result<(), ParseError> {
+        if self.options.trailing_commas {
+            return Ok(());
+        }
+        match self.peek()? {
+            Some(&(ref token, span)) if token == close => Err(self.unexpected(span)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns true if nothing but whitespace is left.
+    pub(crate) fn is_at_end(&mut self) -> bool {
+        self.peeked.is_none() && self.tokens.is_at_end()
+    }
+
+    /// Errors if anything but whitespace is left after the parsed value.
+    pub(crate) fn finish(&mut self) -> Result<(), ParseError> {
+        if let Some((_, span)) = &self.peeked {
+            return Err(ParseError::TrailingCharacters { pos: span.start });
+        }
+        if self.tokens.is_at_end() {
+            Ok(())
+        } else {
+            Err(ParseError::TrailingCharacters {
+                pos: self.tokens.position(),
+            })
+        }
+    }
+
+    /// Parses as much as possible instead of stopping at the first error.
+    ///
+    /// Missing values become `Null`, unclosed containers are closed at the end
+    /// of the input and stray tokens are skipped. Every problem met on the way
+    /// is reported in the returned list.
+    pub(crate) fn parse_recovering(&mut self) -> (JsonValue, Vec<ParseError>) {
+        let mut errors = vec![];
+        let value = self.recover_value(&mut errors);
+        if let Some(&(_, span)) = self.peek_recovering(&mut errors) {
+            errors.push(ParseError::TrailingCharacters { pos: span.start });
+        }
+        (value, errors)
+    }
+
+    fn peek_recovering(&mut self, errors: &mut Vec<ParseError>) -> Option<&(Token, Span)> {
+        while let Err(e) = self.peek() {
+            push_error(errors, e);
+            self.tokens.skip_char();
+        }
+        self.peeked.as_ref()
+    }
+
+    fn recover_value(&mut self, errors: &mut Vec<ParseError>) -> JsonValue {
+        match self.peek_recovering(errors) {
+            Some((Token::LBracket, _)) => self.recover_array(errors),
+            Some((Token::LBrace, _)) => self.recover_object(errors),
+            // Left for the enclosing container to deal with
+            Some(&(Token::Comma | Token::RBracket | Token::RBrace, span)) => {
+                push_error(errors, self.unexpected(span));
+                JsonValue::Null
+            }
+            Some(&(Token::Colon, span)) => {
+                push_error(errors, self.unexpected(span));
+                self.peeked = None;
+                JsonValue::Null
+            }
+            Some(_) => self.parse_value().unwrap_or_else(|e| {
+                push_error(errors, e);
+                JsonValue::Null
+            }),
+            None => {
+                push_error(errors, ParseError::UnexpectedEof);
+                JsonValue::Null
+            }
+        }
+    }
+
+    fn recover_array(&mut self, errors: &mut Vec<ParseError>) -> JsonValue {
+        self.peeked = None;
+        let mut result = vec![];
+        loop {
+            match self.peek_recovering(errors) {
+                Some((Token::RBracket, _)) => {
+                    self.peeked = None;
+                    break;
+                }
+                None => {
+                    push_error(errors, ParseError::UnexpectedEof);
+                    break;
+                }
+                Some(_) => result.push(self.recover_value(errors)),
+            }
+
+            match self.peek_recovering(errors) {
+                Some((Token::Comma, _)) => self.peeked = None,
+                Some((Token::RBracket, _)) => {
+                    self.peeked = None;
+                    break;
+                }
+                Some(&(_, span)) => {
+                    push_error(errors, self.expected(']', span));
+                    self.peeked = None;
+                }
+                None => {
+                    push_error(errors, ParseError::UnexpectedEof);
+                    break;
+                }
+            }
+        }
+        JsonValue::Array(result)
+    }
+
+    fn recover_object(&mut self, errors: &mut Vec<ParseError>) -> JsonValue {
+        self.peeked = None;
+        let mut map = Map::new();
+        loop {
+            let key = match self.peek_recovering(errors) {
+                Some((Token::RBrace, _)) => {
+                    self.peeked = None;
+                    break;
+                }
+                None => {
+                    push_error(errors, ParseError::UnexpectedEof);
+                    break;
+                }
+                Some((Token::String(_), _)) => match self.peeked.take() {
+                    Some((Token::String(key), _)) => key,
+                    _ => unreachable!(),
+                },
+                Some(&(_, span)) => {
+                    push_error(errors, ParseError::ExpectedKey { pos: span.start });
+                    self.peeked = None;
+                    continue;
+                }
+            };
+
+            match self.peek_recovering(errors) {
+                Some((Token::Colon, _)) => self.peeked = None,
+                Some(&(_, span)) => push_error(errors, self.expected(':', span)),
+                None => {}
+            }
+            let value = self.recover_value(errors);
+            map.insert(key, value);
+
+            match self.peek_recovering(errors) {
+                Some((Token::Comma, _)) => self.peeked = None,
+                Some((Token::RBrace, _)) => {
+                    self.peeked = None;
+                    break;
+                }
+                Some(&(_, span)) => {
+                    push_error(errors, self.expected('}', span));
+                    self.peeked = None;
+                }
+                None => {
+                    push_error(errors, ParseError::UnexpectedEof);
+                    break;
+                }
+            }
+        }
+        JsonValue::Obj(map)
+    }
+
+    fn expected(&self, expected: char, span: Span) -> ParseError {
+        match self.unexpected(span) {
+            ParseError::UnexpectedChar { ch, pos } => ParseError::Expected {
+                expected,
+                found: ch,
+                pos,
+            },
+            e => e,
+        }
+    }
+}
+
+/// Records an error, without repeating the end of input over and over while
+/// unwinding out of unclosed containers.
+fn push_error(errors: &mut Vec<ParseError>, e: ParseError) {
+    if e == ParseError::UnexpectedEof && errors.last() == Some(&e) {
+        return;
+    }
+    errors.push(e);
+}
+
+/// Rounds to `precision` significant digits, going through the exponent
+/// notation so the result doesn't depend on the magnitude of the value.
+fn round_to_precision(value: f64, precision: u32) -> f64 {
+    let digits = precision.max(1) as usize - 1;
+    format!("{:.*e}", digits, value).parse().unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_string() {
+        let input = "Testing parse_string()";
+        let mut parser = Parser::new(format!("\"{}\"", input));
+        match parser.parse_string() {
+            Ok(JsonValue::String(value)) => assert_eq!(value, input),
+            _ => panic!("Expected String"),
+        };
+    }
+
+    #[test]
+    fn test_parse_null() {
+        let mut parser = Parser::new("null".to_string());
+        assert!(matches!(parser.parse_null(), Ok(JsonValue::Null)));
+
+        let mut parser = Parser::new("nul".to_string());
+        assert!(parser.parse_null().is_err());
+
+        assert_eq!(
+            crate::from_str("nullx"),
+            Err(ParseError::UnexpectedChar { ch: 'x', pos: 4 })
+        );
+        assert_eq!(
+            crate::from_str("null,"),
+            Err(ParseError::TrailingCharacters { pos: 4 })
+        );
+    }
+
+    #[test]
+    fn test_parse_bool() {
+        let mut parser = Parser::new("true".to_string());
+        match parser.parse_bool() {
+            Ok(JsonValue::Bool(value)) => assert!(value),
+            _ => panic!("Expected True"),
+        };
+
+        let mut parser = Parser::new("false".to_string());
+        match parser.parse_bool() {
+            Ok(JsonValue::Bool(value)) => assert!(!value),
+            _ => panic!("Expected False"),
+        };
+
+        let mut parser = Parser::new("fale".to_string());
+        assert!(parser.parse_bool().is_err());
+
+        assert_eq!(
+            crate::from_str("truex"),
+            Err(ParseError::UnexpectedChar { ch: 'x', pos: 4 })
+        );
+        assert_eq!(
+            crate::from_str("[falsey]"),
+            Err(ParseError::UnexpectedChar { ch: 'y', pos: 6 })
+        );
+        assert_eq!(
+            crate::from_str("[true,false]"),
+            Ok(JsonValue::Array(vec![
+                JsonValue::Bool(true),
+                JsonValue::Bool(false)
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_number() {
+        let mut parser = Parser::new("01234 abc".to_string());
+        match parser.parse_number() {
+            Ok(JsonValue::Number(value)) => assert_eq!(value, 1234_f64),
+            _ => panic!("Expected number: 1234"),
+        };
+
+        let mut parser = Parser::new("false".to_string());
+        assert!(parser.parse_number().is_err());
+
+        let mut parser = Parser::new("-12.5e-1".to_string());
+        assert_eq!(parser.parse_number(), Ok(JsonValue::Number(-1.25)));
+
+        let mut parser = Parser::new("1.".to_string());
+        assert_eq!(parser.parse_number(), Err(ParseError::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let mut parser = Parser::new(r#"{"a": 1} [2]  tail"#.to_string());
+        assert_eq!(parser.parse_value(), crate::from_str(r#"{"a": 1}"#));
+        assert_eq!(parser.position(), 8);
+        assert_eq!(parser.remaining(), " [2]  tail");
+
+        assert_eq!(parser.parse_value(), crate::from_str("[2]"));
+        assert_eq!(parser.remaining(), "  tail");
+        assert!(parser.parse_value().is_err());
+    }
+
+    #[test]
+    fn test_raw_keys() {
+        let options = ParserOptions {
+            raw_keys: vec!["payload".to_string()],
+            ..Default::default()
+        };
+        let input =
+            r#"{"id": 1, "payload": {"a": [1, 2.50] , "b":null}, "more": [{"payload": 7}]}"#;
+        let value = crate::from_str_with_options(input, options.clone()).unwrap();
+        assert_eq!(
+            value.get("payload"),
+            Some(&JsonValue::RawJson(
+                r#"{"a": [1, 2.50] , "b":null}"#.to_string()
+            ))
+        );
+        assert_eq!(
+            value.to_string(),
+            r#"{"id":1,"payload":{"a": [1, 2.50] , "b":null},"more":[{"payload":7}]}"#
+        );
+
+        let lenient = ParserOptions {
+            raw_keys: vec!["payload".to_string()],
+            ..ParserOptions::lenient()
+        };
+        let value =
+            crate::from_str_with_options("{\"payload\": [1, /* two */ 2,]}", lenient).unwrap();
+        assert_eq!(value.to_string(), "{\"payload\":[1, /* two */ 2,]}");
+        assert!(crate::from_str(&value.to_string()).is_err());
+
+        assert_eq!(
+            crate::from_str_with_options(r#"{"payload": [1 2]}"#, options),
+            Err(ParseError::Expected {
+                expected: ']',
+                found: '2',
+                pos: 15
+            })
+        );
+    }
+
+    #[test]
+    fn test_on_progress_cancel() {
+        let input = format!("[{}]", vec!["1"; 10_000].join(","));
+        let len = input.len();
+
+        let mut parser = Parser::new(input.clone());
+        parser.on_progress(1000, |pos| pos < 5000);
+        match parser.parse_value() {
+            Err(ParseError::Cancelled { pos }) => assert!((5000..len).contains(&pos)),
+            other => panic!("Expected cancellation, got {:?}", other),
+        }
+
+        let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = calls.clone();
+        let mut parser = Parser::new(input);
+        parser.on_progress(1000, move |_| {
+            counter.set(counter.get() + 1);
+            true
+        });
+        assert!(parser.parse_value().is_ok());
+        assert_eq!(calls.get(), len / 1000);
+    }
+
+    #[test]
+    fn test_parse_zero() {
+        for (input, negative) in [("0", false), ("-0", true), ("0.0", false), ("-0.0", true)] {
+            let mut parser = Parser::new(input.to_string());
+            match parser.parse_value() {
+                Ok(JsonValue::Number(value)) => {
+                    assert_eq!(value, 0.0, "{}", input);
+                    assert_eq!(value.is_sign_negative(), negative, "{}", input);
+                }
+                _ => panic!("Expected number: {}", input),
+            }
+        }
+
+        let mut parser = Parser::new("[-0, 0e5, -0.0e-3]".to_string());
+        assert_eq!(parser.parse_value().unwrap().to_string(), "[-0,0,-0]");
+
+        let mut parser = Parser::new("-".to_string());
+        assert_eq!(parser.parse_value(), Err(ParseError::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_parse_recovering() {
+        let mut parser = Parser::new(r#"{"a": 1, "b":"#.to_string());
+        let (value, errors) = parser.parse_recovering();
+
+        let mut expected = Map::new();
+        expected.insert("a".to_string(), JsonValue::Number(1.0));
+        expected.insert("b".to_string(), JsonValue::Null);
+        assert_eq!(value, JsonValue::Obj(expected));
+        assert_eq!(errors, vec![ParseError::UnexpectedEof]);
+
+        let mut parser = Parser::new("[1 : 2, ?]".to_string());
+        let (value, errors) = parser.parse_recovering();
+        assert_eq!(
+            value,
+            JsonValue::Array(vec![JsonValue::Number(1.0), JsonValue::Number(2.0)])
+        );
+        assert_eq!(
+            errors,
+            vec![
+                ParseError::Expected {
+                    expected: ']',
+                    found: ':',
+                    pos: 3
+                },
+                ParseError::UnexpectedChar { ch: '?', pos: 8 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_presize_containers() {
+        let input = format!(
+            r#"{{"items": [{}], "nested": [[1, 2], {{"a": "x,]"}}, []]}}"#,
+            (0..1000)
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        let options = ParserOptions {
+            presize_containers: true,
+            ..Default::default()
+        };
+        let presized = Parser::with_options(input.clone(), options)
+            .parse_value()
+            .unwrap();
+        assert_eq!(presized, Parser::new(input).parse_value().unwrap());
+
+        // One allocation of exactly the right size instead of repeated doubling
+        match presized.get("items") {
+            Some(JsonValue::Array(arr)) => assert_eq!(arr.capacity(), 1000),
+            _ => panic!("Expected array"),
+        }
+        match presized.get("nested") {
+            Some(JsonValue::Array(arr)) => assert_eq!(arr.capacity(), 3),
+            _ => panic!("Expected array"),
+        }
+
+        // Commas and brackets inside comments and single-quoted strings
+        let input = "[ // a, b]\n 'c, ]', /* d, ] */ 1 # e, ]\n, {'f': [2, 3]} ]";
+        let options = ParserOptions {
+            presize_containers: true,
+            ..ParserOptions::lenient()
+        };
+        let presized = Parser::with_options(input.to_string(), options)
+            .parse_value()
+            .unwrap();
+        assert_eq!(
+            presized,
+            Parser::with_options(input.to_string(), ParserOptions::lenient())
+                .parse_value()
+                .unwrap()
+        );
+        match &presized {
+            JsonValue::Array(arr) => assert_eq!(arr.capacity(), 3),
+            _ => panic!("Expected array"),
+        }
+        let options = ParserOptions {
+            presize_containers: true,
+            ..ParserOptions::lenient()
+        };
+        let value = Parser::with_options("[ /* nothing */ ]".to_string(), options)
+            .parse_value()
+            .unwrap();
+        match value {
+            JsonValue::Array(arr) => assert_eq!(arr.capacity(), 0),
+            _ => panic!("Expected array"),
+        }
+    }
+
+    #[test]
+    fn test_big_integers_as_strings() {
+        let options = ParserOptions {
+            big_integers: BigIntegers::String,
+            leading_plus: true,
+            ..Default::default()
+        };
+        let parse = |input: &str| crate::from_str_with_options(input, options.clone()).unwrap();
+        assert_eq!(
+            parse("9007199254740993"),
+            JsonValue::String("9007199254740993".to_string())
+        );
+        assert_eq!(
+            parse("[-123456789012345678901234567890, +9007199254740992]"),
+            JsonValue::Array(vec![
+                JsonValue::String("-123456789012345678901234567890".to_string()),
+                JsonValue::String("9007199254740992".to_string()),
+            ])
+        );
+        // Still exact, or not an integer literal
+        assert_eq!(
+            parse("[9007199254740991, 9007199254740993.0, 1e300]"),
+            JsonValue::Array(vec![
+                JsonValue::Number(9007199254740991.0),
+                JsonValue::Number(9007199254740992.0),
+                JsonValue::Number(1e300),
+            ])
+        );
+        assert_eq!(
+            crate::from_str("9007199254740993"),
+            Ok(JsonValue::Number(9007199254740992.0))
+        );
+    }
+
+    #[test]
+    fn test_big_integers_exact() {
+        let options = ParserOptions {
+            big_integers: BigIntegers::Exact,
+            ..Default::default()
+        };
+        let parse = |input: &str| crate::from_str_with_options(input, options.clone()).unwrap();
+        assert_eq!(parse("9007199254740993"), JsonValue::Int(9007199254740993));
+        assert_eq!(parse("-9223372036854775808"), JsonValue::Int(i64::MIN));
+        assert_eq!(
+            parse("18446744073709551616"),
+            JsonValue::RawNumber("18446744073709551616".to_string())
+        );
+        let value = parse(r#"{"id": 123456789012345678901234567890, "n": 12, "x": 1.5}"#);
+        assert_eq!(
+            value.to_string(),
+            r#"{"id":123456789012345678901234567890,"n":12,"x":1.5}"#
+        );
+        assert_eq!(value.get("n"), Some(&JsonValue::Number(12.0)));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn test_number_precision() {
+        let options = ParserOptions {
+            number_precision: Some(3),
+            ..Default::default()
+        };
+        let mut parser = Parser::with_options("3.14159265".to_string(), options);
+        assert_eq!(parser.parse_value(), Ok(JsonValue::Number(3.14)));
+
+        let mut parser = Parser::new("3.14159265".to_string());
+        assert_eq!(parser.parse_value(), Ok(JsonValue::Number(3.14159265)));
+    }
+
+    #[test]
+    fn test_parse_array() {
+        let mut parser = Parser::new("[1,32,\"abc\", null  ]".to_string());
+        let expected_result = vec![
+            JsonValue::Number(1_f64),
+            JsonValue::Number(32_f64),
+            JsonValue::String("abc".to_string()),
+            JsonValue::Null,
+        ];
+        match parser.parse_array() {
+            Ok(JsonValue::Array(value)) => assert_eq!(value, expected_result),
+            _ => panic!("Expected array"),
+        };
+
+        let mut parser = Parser::new("false".to_string());
+        assert!(parser.parse_array().is_err());
+
+        let mut parser = Parser::new("[]".to_string());
+        match parser.parse_array() {
+            Ok(JsonValue::Array(value)) => assert_eq!(value, vec![]),
+            _ => panic!("Expected an empty array"),
+        }
+    }
+
+    #[test]
+    fn test_stray_characters_between_tokens() {
+        let cases = [
+            ("[1; 2]", 2),
+            ("[1 ;2]", 3),
+            ("[1,;2]", 3),
+            ("[;1]", 1),
+            (r#"{"a" ; 1}"#, 5),
+            (r#"{"a":;1}"#, 5),
+            (r#"{"a":1;}"#, 6),
+            (r#"{;"a":1}"#, 1),
+        ];
+        for (input, pos) in cases {
+            assert_eq!(
+                crate::from_str(input),
+                Err(ParseError::UnexpectedChar { ch: ';', pos }),
+                "input: {:?}",
+                input
+            );
+        }
+        assert_eq!(
+            crate::from_str("[1]; "),
+            Err(ParseError::TrailingCharacters { pos: 3 })
+        );
+        assert!(crate::from_str("\t[ 1 ,\r\n2 ]\n").is_ok());
+    }
+
+    #[test]
+    fn test_parse_object() {
+        let mut parser = Parser::new(
+            r#"{
+                "one" : 2,
+                "two" : [2, null, false],
+                "three": "third value",
+            }"#
+            .to_string(),
+        );
+
+        let mut expected_result = Map::new();
+        expected_result.insert("one".to_string(), JsonValue::Number(2.0));
+        expected_result.insert(
+            "two".to_string(),
+            JsonValue::Array(vec![
+                JsonValue::Number(2.0),
+                JsonValue::Null,
+                JsonValue::Bool(false),
+            ]),
+        );
+        expected_result.insert(
+            "three".to_string(),
+            JsonValue::String("third value".to_string()),
+        );
+
+        match parser.parse_object() {
+            Ok(JsonValue::Obj(value)) => assert_eq!(value, expected_result),
+            _ => panic!("Expected array"),
+        };
+
+        let mut parser = Parser::new("false".to_string());
+        assert!(parser.parse_object().is_err());
+
+        let mut parser = Parser::new("{}".to_string());
+        match parser.parse_object() {
+            Ok(JsonValue::Obj(value)) => assert_eq!(value, Map::new()),
+            _ => panic!("Expected an empty map"),
+        }
+    }
+}
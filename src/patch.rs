@@ -0,0 +1,446 @@
+use std::fmt;
+
+use crate::pointer::{escape_token, parse_index};
+use crate::{JsonValue, Map};
+
+/// Why `JsonValue::apply_patch` failed. `index` is the position of the
+/// operation in the patch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    /// The patch or an operation in it isn't shaped as RFC 6902 requires,
+    /// e.g. an unknown `op` or a missing `value`.
+    Malformed { index: usize, reason: String },
+    /// The path, or the `from` of a move or copy, doesn't resolve.
+    PathNotFound { index: usize, path: String },
+    /// A `test` operation found a different value.
+    TestFailed { index: usize, path: String },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::Malformed { index, reason } => {
+                write!(f, "operation {}: {}", index, reason)
+            }
+            PatchError::PathNotFound { index, path } => {
+                write!(f, "operation {}: nothing at \"{}\"", index, path)
+            }
+            PatchError::TestFailed { index, path } => {
+                write!(f, "operation {}: test failed at \"{}\"", index, path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl JsonValue {
+    /// Applies a JSON Patch (RFC 6902): an array of `add`, `remove`,
+    /// `replace`, `move`, `copy` and `test` operations. Either all of them
+    /// apply or, on error, `self` is left unchanged.
+    pub fn apply_patch(&mut self, patch: &JsonValue) -> Result<(), PatchError> {
+        let JsonValue::Array(ops) = patch else {
+            return Err(PatchError::Malformed {
+                index: 0,
+                reason: format!(
+                    "expected an array of operations, found {}",
+                    patch.type_name()
+                ),
+            });
+        };
+        let mut doc = self.clone();
+        for (index, op) in ops.iter().enumerate() {
+            apply_op(&mut doc, op, index)?;
+        }
+        *self = doc;
+        Ok(())
+    }
+
+    /// Computes a JSON Patch (RFC 6902) that turns `self` into `target`, as
+    /// an array of `add`, `remove` and `replace` operations.
+    ///
+    /// Objects and arrays are compared entry by entry, anything else that
+    /// differs is replaced whole. Array elements are matched by index, so
+    /// an insertion near the start shows up as a run of replacements.
+    pub fn create_patch(&self, target: &JsonValue) -> JsonValue {
+        let mut ops = vec![];
+        diff(self, target, &mut String::new(), &mut ops);
+        JsonValue::Array(ops)
+    }
+}
+
+fn diff(from: &JsonValue, to: &JsonValue, path: &mut String, ops: &mut Vec<JsonValue>) {
+    if from == to {
+        return;
+    }
+    let len = path.len();
+    match (from, to) {
+        (JsonValue::Obj(a), JsonValue::Obj(b)) => {
+            for (key, value) in a {
+                path.push('/');
+                path.push_str(&escape_token(key));
+                match b.get(key) {
+                    Some(target) => diff(value, target, path, ops),
+                    None => ops.push(op("remove", path, None)),
+                }
+                path.truncate(len);
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                path.push('/');
+                path.push_str(&escape_token(key));
+                ops.push(op("add", path, Some(value)));
+                path.truncate(len);
+            }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            for (i, (value, target)) in a.iter().zip(b).enumerate() {
+                path.push_str(&format!("/{}", i));
+                diff(value, target, path, ops);
+                path.truncate(len);
+            }
+            for (i, value) in b.iter().enumerate().skip(a.len()) {
+                path.push_str(&format!("/{}", i));
+                ops.push(op("add", path, Some(value)));
+                path.truncate(len);
+            }
+            // From the end, so the indices still to remove don't move
+            for i in (b.len()..a.len()).rev() {
+                path.push_str(&format!("/{}", i));
+                ops.push(op("remove", path, None));
+                path.truncate(len);
+            }
+        }
+        _ => ops.push(op("replace", path, Some(to))),
+    }
+}
+
+fn apply_op(doc: &mut JsonValue, op: &JsonValue, index: usize) -> Result<(), PatchError> {
+    let malformed = |reason: String| PatchError::Malformed { index, reason };
+    let member = |name: &str| match op.get(name) {
+        Some(JsonValue::String(s)) => Ok(s.as_str()),
+        _ => Err(malformed(format!("missing string member \"{}\"", name))),
+    };
+    let value = || {
+        op.get("value")
+            .cloned()
+            .ok_or_else(|| malformed("missing member \"value\"".to_string()))
+    };
+    let path = member("path")?;
+    let tokens =
+        parse_pointer(path).ok_or_else(|| malformed(format!("bad pointer \"{}\"", path)))?;
+    let not_found = |path: &str| PatchError::PathNotFound {
+        index,
+        path: path.to_string(),
+    };
+    let from = || {
+        let from = member("from")?;
+        let tokens =
+            parse_pointer(from).ok_or_else(|| malformed(format!("bad pointer \"{}\"", from)))?;
+        Ok((from, tokens))
+    };
+
+    match member("op")? {
+        "add" => add(doc, &tokens, value()?).ok_or_else(|| not_found(path)),
+        "remove" => remove(doc, &tokens)
+            .map(drop)
+            .ok_or_else(|| not_found(path)),
+        "replace" => {
+            let target = pointer_mut(doc, &tokens).ok_or_else(|| not_found(path))?;
+            *target = value()?;
+            Ok(())
+        }
+        "move" => {
+            let (from, from_tokens) = from()?;
+            if tokens.len() > from_tokens.len() && tokens.starts_with(&from_tokens) {
+                return Err(malformed(format!("can't move \"{}\" into itself", from)));
+            }
+            let moved = remove(doc, &from_tokens).ok_or_else(|| not_found(from))?;
+            add(doc, &tokens, moved).ok_or_else(|| not_found(path))
+        }
+        "copy" => {
+            let (from, from_tokens) = from()?;
+            let copied = pointer_mut(doc, &from_tokens)
+                .cloned()
+                .ok_or_else(|| not_found(from))?;
+            add(doc, &tokens, copied).ok_or_else(|| not_found(path))
+        }
+        "test" => match doc.pointer(path) {
+            Some(found) if *found == value()? => Ok(()),
+            Some(_) => Err(PatchError::TestFailed {
+                index,
+                path: path.to_string(),
+            }),
+            None => Err(not_found(path)),
+        },
+        other => Err(malformed(format!("unknown op \"{}\"", other))),
+    }
+}
+
+/// Splits a pointer into its unescaped tokens, `None` if it isn't one.
+fn parse_pointer(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(vec![]);
+    }
+    let rest = pointer.strip_prefix('/')?;
+    Some(
+        rest.split('/')
+            .map(|t| t.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+fn pointer_mut<'a>(doc: &'a mut JsonValue, tokens: &[String]) -> Option<&'a mut JsonValue> {
+    tokens.iter().try_fold(doc, |target, token| match target {
+        JsonValue::Obj(map) => map.get_mut(token),
+        JsonValue::Array(arr) => arr.get_mut(parse_index(token)?),
+        _ => None,
+    })
+}
+
+/// Sets an object member or inserts into an array, where `-` means the end.
+fn add(doc: &mut JsonValue, tokens: &[String], value: JsonValue) -> Option<()> {
+    let Some((last, parent)) = tokens.split_last() else {
+        *doc = value;
+        return Some(());
+    };
+    match pointer_mut(doc, parent)? {
+        JsonValue::Obj(map) => {
+            map.insert(last.clone(), value);
+        }
+        JsonValue::Array(arr) if last == "-" => arr.push(value),
+        JsonValue::Array(arr) => {
+            let i = parse_index(last).filter(|i| *i <= arr.len())?;
+            arr.insert(i, value);
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+fn remove(doc: &mut JsonValue, tokens: &[String]) -> Option<JsonValue> {
+    let (last, parent) = tokens.split_last()?;
+    match pointer_mut(doc, parent)? {
+        JsonValue::Obj(map) => map.shift_remove(last),
+        JsonValue::Array(arr) => {
+            let i = parse_index(last).filter(|i| *i < arr.len())?;
+            Some(arr.remove(i))
+        }
+        _ => None,
+    }
+}
+
+fn op(name: &str, path: &str, value: Option<&JsonValue>) -> JsonValue {
+    let mut map = Map::new();
+    map.insert("op".to_string(), JsonValue::String(name.to_string()));
+    map.insert("path".to_string(), JsonValue::String(path.to_string()));
+    if let Some(value) = value {
+        map.insert("value".to_string(), value.clone());
+    }
+    JsonValue::Obj(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_create_patch() {
+        let from = from_str(
+            r#"{"name": "a", "tags": ["x", "y", "z"], "old": 1, "nested": {"k": [1], "a/b": true}}"#,
+        )
+        .unwrap();
+        let to = from_str(
+            r#"{"name": "b", "tags": ["x"], "nested": {"k": [1, {"n": null}], "a/b": false}, "new": [2]}"#,
+        )
+        .unwrap();
+        let patch = from.create_patch(&to);
+        assert_eq!(
+            patch,
+            from_str(
+                r#"[
+                    {"op": "replace", "path": "/name", "value": "b"},
+                    {"op": "remove", "path": "/tags/2"},
+                    {"op": "remove", "path": "/tags/1"},
+                    {"op": "remove", "path": "/old"},
+                    {"op": "add", "path": "/nested/k/1", "value": {"n": null}},
+                    {"op": "replace", "path": "/nested/a~1b", "value": false},
+                    {"op": "add", "path": "/new", "value": [2]}
+                ]"#
+            )
+            .unwrap()
+        );
+
+        let mut doc = from.clone();
+        doc.apply_patch(&patch).unwrap();
+        assert_eq!(doc, to);
+    }
+
+    fn patched(doc: &str, patch: &str) -> Result<JsonValue, PatchError> {
+        let mut doc = from_str(doc).unwrap();
+        doc.apply_patch(&from_str(patch).unwrap())?;
+        Ok(doc)
+    }
+
+    #[test]
+    fn test_add() {
+        assert_eq!(
+            patched(
+                r#"{"a": [1, 2]}"#,
+                r#"[{"op": "add", "path": "/b", "value": {}},
+                    {"op": "add", "path": "/b/c~1d", "value": true},
+                    {"op": "add", "path": "/a/1", "value": 9},
+                    {"op": "add", "path": "/a/-", "value": 3}]"#
+            ),
+            Ok(from_str(r#"{"a": [1, 9, 2, 3], "b": {"c/d": true}}"#).unwrap())
+        );
+        assert_eq!(
+            patched(r#"[1]"#, r#"[{"op": "add", "path": "/2", "value": 3}]"#),
+            Err(PatchError::PathNotFound {
+                index: 0,
+                path: "/2".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        assert_eq!(
+            patched(
+                r#"{"a": [1, 2, 3], "b": 0}"#,
+                r#"[{"op": "remove", "path": "/a/0"}, {"op": "remove", "path": "/b"}]"#
+            ),
+            Ok(from_str(r#"{"a": [2, 3]}"#).unwrap())
+        );
+        assert!(patched(r#"{}"#, r#"[{"op": "remove", "path": "/x"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_replace() {
+        assert_eq!(
+            patched(
+                r#"{"a": {"b": 1}, "c": [0]}"#,
+                r#"[{"op": "replace", "path": "/a/b", "value": [2]},
+                    {"op": "replace", "path": "/c/0", "value": null}]"#
+            ),
+            Ok(from_str(r#"{"a": {"b": [2]}, "c": [null]}"#).unwrap())
+        );
+        assert!(patched(r#"{}"#, r#"[{"op": "replace", "path": "/x", "value": 1}]"#).is_err());
+    }
+
+    #[test]
+    fn test_move() {
+        assert_eq!(
+            patched(
+                r#"{"a": {"b": 1}, "c": [2, 3]}"#,
+                r#"[{"op": "move", "from": "/a/b", "path": "/c/0"},
+                    {"op": "move", "from": "/c", "path": "/d"}]"#
+            ),
+            Ok(from_str(r#"{"a": {}, "d": [1, 2, 3]}"#).unwrap())
+        );
+        assert_eq!(
+            patched(
+                r#"{"a": {}}"#,
+                r#"[{"op": "move", "from": "/a", "path": "/a/b"}]"#
+            ),
+            Err(PatchError::Malformed {
+                index: 0,
+                reason: "can't move \"/a\" into itself".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_copy() {
+        assert_eq!(
+            patched(
+                r#"{"a": {"b": [1]}}"#,
+                r#"[{"op": "copy", "from": "/a/b", "path": "/c"}]"#
+            ),
+            Ok(from_str(r#"{"a": {"b": [1]}, "c": [1]}"#).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_malformed_from() {
+        for op in ["move", "copy"] {
+            let patch = format!(r#"[{{"op": "{}", "from": "a/b", "path": "/c"}}]"#, op);
+            assert_eq!(
+                patched(r#"{"a": {"b": 1}}"#, &patch),
+                Err(PatchError::Malformed {
+                    index: 0,
+                    reason: "bad pointer \"a/b\"".to_string()
+                }),
+                "{}",
+                op
+            );
+        }
+    }
+
+    #[test]
+    fn test_test_op() {
+        let doc = r#"{"a": [1, "x"]}"#;
+        assert!(patched(doc, r#"[{"op": "test", "path": "/a/1", "value": "x"}]"#).is_ok());
+
+        // Nothing is applied when a later operation fails
+        let mut value = from_str(doc).unwrap();
+        let patch = from_str(
+            r#"[{"op": "add", "path": "/b", "value": 1},
+                {"op": "test", "path": "/a/0", "value": 2}]"#,
+        )
+        .unwrap();
+        let error = value.apply_patch(&patch).unwrap_err();
+        assert_eq!(
+            error,
+            PatchError::TestFailed {
+                index: 1,
+                path: "/a/0".to_string()
+            }
+        );
+        assert_eq!(error.to_string(), "operation 1: test failed at \"/a/0\"");
+        assert_eq!(value, from_str(doc).unwrap());
+    }
+
+    #[test]
+    fn test_malformed() {
+        let malformed = |patch| match patched("{}", patch) {
+            Err(PatchError::Malformed { reason, .. }) => reason,
+            other => panic!("Expected malformed, got {:?}", other),
+        };
+        assert_eq!(
+            malformed(r#"[{"op": "nope", "path": ""}]"#),
+            "unknown op \"nope\""
+        );
+        assert_eq!(
+            malformed(r#"[{"op": "add", "path": "/a"}]"#),
+            "missing member \"value\""
+        );
+        assert_eq!(
+            malformed(r#"[{"op": "add", "path": "a", "value": 1}]"#),
+            "bad pointer \"a\""
+        );
+        assert_eq!(
+            malformed(r#"[{"path": ""}]"#),
+            "missing string member \"op\""
+        );
+        assert_eq!(
+            malformed("{}"),
+            "expected an array of operations, found object"
+        );
+    }
+
+    #[test]
+    fn test_patch_edge_cases() {
+        let value = from_str(r#"{"a": [1, 2]}"#).unwrap();
+        assert_eq!(value.create_patch(&value), JsonValue::Array(vec![]));
+
+        let patch = value.create_patch(&JsonValue::Null);
+        assert_eq!(
+            patch,
+            from_str(r#"[{"op": "replace", "path": "", "value": null}]"#).unwrap()
+        );
+        let mut doc = value.clone();
+        doc.apply_patch(&patch).unwrap();
+        assert_eq!(doc, JsonValue::Null);
+    }
+}
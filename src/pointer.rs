@@ -0,0 +1,307 @@
+use std::fmt;
+
+use crate::{JsonValue, Map};
+
+/// One step of a path into a tree: an object key or an array index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// An index in a `set_in` path past the end of its array, which would leave
+/// a hole. `segment` is its position in the path, and `len` the length of
+/// the array there, 0 for one `set_in` would create.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetError {
+    pub segment: usize,
+    pub index: usize,
+    pub len: usize,
+}
+
+impl fmt::Display for SetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "path segment {}: index {} is past the end of an array of length {}",
+            self.segment, self.index, self.len
+        )
+    }
+}
+
+impl std::error::Error for SetError {}
+
+impl JsonValue {
+    /// Looks up a value by following `path`. A key only matches in an
+    /// object and an index only in an array.
+    pub fn get_in(&self, path: &[PathSegment]) -> Option<&JsonValue> {
+        path.iter()
+            .try_fold(self, |target, segment| match (target, segment) {
+                (JsonValue::Obj(map), PathSegment::Key(key)) => map.get(*key),
+                (JsonValue::Array(arr), PathSegment::Index(i)) => arr.get(*i),
+                _ => None,
+            })
+    }
+
+    /// Sets the value at `path`, creating whatever objects and arrays are
+    /// missing on the way. Anything on the way that isn't the kind of
+    /// container its segment needs, a scalar or an array where a key follows,
+    /// is replaced by an empty one, dropping what it held. An index equal to
+    /// the length of its array appends, as `-` does in a JSON Patch; one past
+    /// that is an error, and the tree is left unchanged.
+    pub fn set_in(&mut self, path: &[PathSegment], value: JsonValue) -> Result<(), SetError> {
+        check_indices(self, path)?;
+        let mut target = self;
+        for segment in path {
+            target = match segment {
+                PathSegment::Key(key) => {
+                    if !matches!(target, JsonValue::Obj(_)) {
+                        *target = JsonValue::Obj(Map::new());
+                    }
+                    let JsonValue::Obj(map) = target else {
+                        unreachable!()
+                    };
+                    map.entry(key.to_string()).or_insert(JsonValue::Null)
+                }
+                PathSegment::Index(i) => {
+                    if !matches!(target, JsonValue::Array(_)) {
+                        *target = JsonValue::Array(vec![]);
+                    }
+                    let JsonValue::Array(arr) = target else {
+                        unreachable!()
+                    };
+                    if *i == arr.len() {
+                        arr.push(JsonValue::Null);
+                    }
+                    &mut arr[*i]
+                }
+            };
+        }
+        *target = value;
+        Ok(())
+    }
+
+    /// Looks up a value by JSON Pointer (RFC 6901), e.g. `/users/0/name`.
+    ///
+    /// The empty pointer is the value itself. In a segment `~1` stands for `/`
+    /// and `~0` for `~`. Returns `None` if any segment doesn't resolve.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        let rest = pointer.strip_prefix('/')?;
+        rest.split('/').try_fold(self, |target, segment| {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            match target {
+                JsonValue::Obj(map) => map.get(&segment),
+                JsonValue::Array(arr) => arr.get(parse_index(&segment)?),
+                _ => None,
+            }
+        })
+    }
+
+    /// JSON Pointers to every node for which `pred` holds, containers
+    /// before their contents and in document order. The root is `""`.
+    pub fn find_paths(&self, pred: impl Fn(&JsonValue) -> bool) -> Vec<String> {
+        let mut paths = vec![];
+        self.find_paths_into(&pred, &mut String::new(), &mut paths);
+        paths
+    }
+
+    fn find_paths_into(
+        &self,
+        pred: &impl Fn(&JsonValue) -> bool,
+        path: &mut String,
+        paths: &mut Vec<String>,
+    ) {
+        if pred(self) {
+            paths.push(path.clone());
+        }
+        let len = path.len();
+        match self {
+            JsonValue::Obj(map) => {
+                for (key, value) in map {
+                    path.push('/');
+                    path.push_str(&escape_token(key));
+                    value.find_paths_into(pred, path, paths);
+                    path.truncate(len);
+                }
+            }
+            JsonValue::Array(arr) => {
+                for (i, value) in arr.iter().enumerate() {
+                    path.push('/');
+                    path.push_str(&i.to_string());
+                    value.find_paths_into(pred, path, paths);
+                    path.truncate(len);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Escapes a key for use as a pointer segment: `~` as `~0`, `/` as `~1`.
+pub(crate) fn escape_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Checks that `set_in` can follow `path` from `target` without leaving a
+/// hole in an array. Containers it would create start out empty.
+fn check_indices(target: &JsonValue, path: &[PathSegment]) -> Result<(), SetError> {
+    let mut target = Some(target);
+    for (segment, step) in path.iter().enumerate() {
+        target = match (target, step) {
+            (Some(JsonValue::Obj(map)), PathSegment::Key(key)) => map.get(*key),
+            (_, PathSegment::Key(_)) => None,
+            (target, PathSegment::Index(i)) => {
+                let arr: &[JsonValue] = match target {
+                    Some(JsonValue::Array(arr)) => arr,
+                    _ => &[],
+                };
+                if *i > arr.len() {
+                    return Err(SetError {
+                        segment,
+                        index: *i,
+                        len: arr.len(),
+                    });
+                }
+                arr.get(*i)
+            }
+        };
+    }
+    Ok(())
+}
+
+/// Array indices are plain decimal numbers, without leading zeros or signs.
+pub(crate) fn parse_index(segment: &str) -> Option<usize> {
+    if segment.is_empty()
+        || !segment.bytes().all(|b| b.is_ascii_digit())
+        || (segment.len() > 1 && segment.starts_with('0'))
+    {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathSegment::{Index, Key};
+    use super::{PathSegment, SetError};
+    use crate::{JsonValue, from_str};
+
+    #[test]
+    fn test_get_in() {
+        let value = from_str(r#"{"one": 1, "two": [true, {"three": 3}], "0": "zero"}"#).unwrap();
+        assert_eq!(value.get_in(&[]), Some(&value));
+        assert_eq!(
+            value.get_in(&[Key("two"), Index(1)]),
+            Some(&from_str(r#"{"three": 3}"#).unwrap())
+        );
+        assert_eq!(
+            value.get_in(&[Key("two"), Index(1), Key("three")]),
+            Some(&JsonValue::Number(3.0))
+        );
+        assert_eq!(
+            value.get_in(&[Key("0")]),
+            Some(&JsonValue::String("zero".to_string()))
+        );
+        assert_eq!(value.get_in(&[Index(0)]), None);
+        assert_eq!(value.get_in(&[Key("two"), Key("1")]), None);
+        assert_eq!(value.get_in(&[Key("two"), Index(2)]), None);
+    }
+
+    #[test]
+    fn test_find_paths() {
+        let value =
+            from_str(r#"{"a": "x", "b": ["y", "x", {"c/d": "x", "e~": ["x"]}], "f": {"x": 1}}"#)
+                .unwrap();
+        let target = JsonValue::String("x".to_string());
+        let paths = value.find_paths(|v| *v == target);
+        assert_eq!(paths, ["/a", "/b/1", "/b/2/c~1d", "/b/2/e~0/0"]);
+        for path in &paths {
+            assert_eq!(value.pointer(path), Some(&target));
+        }
+
+        assert_eq!(value.find_paths(|v| v.depth() == 1), ["/b/2/e~0", "/f"]);
+        assert!(value.find_paths(|v| *v == JsonValue::Null).is_empty());
+    }
+
+    #[test]
+    fn test_set_in() {
+        let mut value = from_str("{}").unwrap();
+        let mut set = |path: &[PathSegment], v| value.set_in(path, v).unwrap();
+        set(&[Key("a"), Key("b")], JsonValue::Number(1.0));
+        set(&[Key("a"), Key("c"), Index(0)], JsonValue::Null);
+        set(&[Key("a"), Key("c"), Index(1)], JsonValue::Bool(true));
+        set(&[Key("a"), Key("c"), Index(0)], JsonValue::Number(0.0));
+        set(&[Key("a"), Key("b")], JsonValue::Number(2.0));
+        assert_eq!(
+            value,
+            from_str(r#"{"a": {"b": 2, "c": [0, true]}}"#).unwrap()
+        );
+
+        // The scalar at a/b and the array at a/c are in the way
+        value
+            .set_in(&[Key("a"), Key("b"), Key("d")], JsonValue::Null)
+            .unwrap();
+        value
+            .set_in(&[Key("a"), Key("c"), Key("e")], JsonValue::Null)
+            .unwrap();
+        assert_eq!(
+            value,
+            from_str(r#"{"a": {"b": {"d": null}, "c": {"e": null}}}"#).unwrap()
+        );
+
+        value.set_in(&[], JsonValue::Bool(false)).unwrap();
+        assert_eq!(value, JsonValue::Bool(false));
+    }
+
+    #[test]
+    fn test_set_in_past_the_end() {
+        let mut value = from_str(r#"{"a": [1]}"#).unwrap();
+        let copy = value.clone();
+        let error = |segment, index, len| {
+            Err(SetError {
+                segment,
+                index,
+                len,
+            })
+        };
+        for (path, expected) in [
+            (&[Key("a"), Index(2)][..], error(1, 2, 1)),
+            (&[Key("a"), Index(usize::MAX)], error(1, usize::MAX, 1)),
+            (&[Key("b"), Index(1)], error(1, 1, 0)),
+            (&[Key("b"), Index(0), Index(3)], error(2, 3, 0)),
+            (&[Key("a"), Index(0), Index(1)], error(2, 1, 0)),
+        ] {
+            assert_eq!(value.set_in(path, JsonValue::Null), expected, "{:?}", path);
+            assert_eq!(value, copy);
+        }
+        assert_eq!(
+            error(1, 2, 1).unwrap_err().to_string(),
+            "path segment 1: index 2 is past the end of an array of length 1"
+        );
+        value
+            .set_in(&[Key("b"), Index(0), Index(0)], JsonValue::Null)
+            .unwrap();
+        assert_eq!(value.to_string(), r#"{"a":[1],"b":[[null]]}"#);
+    }
+
+    #[test]
+    fn test_pointer() {
+        let value = from_str(r#"{"users": [{"name": "ann"}], "a/b": 1, "m~n": 2, "": 3}"#).unwrap();
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(
+            value.pointer("/users/0/name"),
+            Some(&JsonValue::String("ann".to_string()))
+        );
+        assert_eq!(value.pointer("/a~1b"), Some(&JsonValue::Number(1.0)));
+        assert_eq!(value.pointer("/m~0n"), Some(&JsonValue::Number(2.0)));
+        assert_eq!(value.pointer("/"), Some(&JsonValue::Number(3.0)));
+
+        assert_eq!(value.pointer("users"), None);
+        assert_eq!(value.pointer("/users/1"), None);
+        assert_eq!(value.pointer("/users/00"), None);
+        assert_eq!(value.pointer("/users/0/name/x"), None);
+    }
+}
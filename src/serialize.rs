@@ -0,0 +1,466 @@
+use std::fmt::Write;
+
+use crate::JsonValue;
+use crate::escape::write_quoted_with;
+use crate::value::write_number;
+
+/// The line break written between lines of indented output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// How `Number`s are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// The fewest digits that read back as the same `f64`.
+    #[default]
+    Shortest,
+    /// Exactly this many digits after the decimal point, rounding as
+    /// needed, so `1.5` with `Fixed(3)` is `1.500`. `NaN` and the infinities
+    /// are written as usual.
+    Fixed(usize),
+}
+
+/// Controls how `JsonValue::to_string_with_options` writes a value. The
+/// default gives the same compact output as `Display`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SerializeOptions {
+    /// Put every element on its own line, indented by this many spaces per
+    /// level. `None` writes everything on one line without spaces.
+    pub indent: Option<usize>,
+    /// Write object keys in sorted order instead of insertion order.
+    pub sort_keys: bool,
+    /// Write non-ASCII characters in strings as `\uXXXX` escapes.
+    pub escape_non_ascii: bool,
+    /// End the output with a newline.
+    pub trailing_newline: bool,
+    /// Used for the newlines of indented output and the trailing newline.
+    pub line_ending: LineEnding,
+    /// Write non-empty arrays and objects nested deeper than this, counted
+    /// as by `JsonValue::depth`, as the string `"..."`. Meant for logs.
+    pub max_depth: Option<usize>,
+    /// With `indent`, write an array or object on one line, as `[1, 2]` or
+    /// `{"a": 1}`, when it fits before this column. Only the ones that don't
+    /// are broken up, outermost first.
+    pub max_width: Option<usize>,
+    /// How numbers are written: shortest round-trip form by default, or with
+    /// fixed decimal places for reports.
+    pub float_format: FloatFormat,
+}
+
+impl JsonValue {
+    pub fn to_string_with_options(&self, options: &SerializeOptions) -> String {
+        let mut out = String::new();
+        // Writing to a String can't fail
+        let _ = write_value(&mut out, self, options, 0);
+        if options.trailing_newline {
+            out.push_str(options.line_ending.as_str());
+        }
+        out
+    }
+
+    /// Byte length of the compact serialization, `to_string().len()`,
+    /// worked out without building the string. For sizing a buffer up front.
+    pub fn serialized_len(&self) -> usize {
+        let mut counter = ByteCounter(0);
+        // Counting can't fail
+        let _ = write!(counter, "{}", self);
+        counter.0
+    }
+
+    /// Writes the elements of an array as newline-delimited JSON, the
+    /// format `from_reader_lines` reads: each one compact, on its own line
+    /// ending with `\n`. `None` if this isn't an array, or if an element
+    /// holds `RawJson` text that spans lines and so can't be one line.
+    pub fn to_json_lines(&self) -> Option<String> {
+        let JsonValue::Array(arr) = self else {
+            return None;
+        };
+        let mut out = String::new();
+        for item in arr {
+            let start = out.len();
+            let _ = write!(out, "{}", item);
+            if out[start..].contains('\n') {
+                return None;
+            }
+            out.push('\n');
+        }
+        Some(out)
+    }
+}
+
+/// A writer that only counts the bytes given to it.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+fn write_value(
+    out: &mut String,
+    value: &JsonValue,
+    options: &SerializeOptions,
+    depth: usize,
+) -> std::fmt::Result {
+    let newline = |out: &mut String, depth: usize| {
+        if let Some(indent) = options.indent {
+            out.push_str(options.line_ending.as_str());
+            out.extend(std::iter::repeat_n(' ', indent * depth));
+        }
+    };
+    match value {
+        JsonValue::Null => out.write_str("null"),
+        JsonValue::Bool(b) => write!(out, "{}", b),
+        JsonValue::Number(n) => match options.float_format {
+            FloatFormat::Fixed(digits) if n.is_finite() => write!(out, "{:.*}", digits, n),
+            _ => write_number(out, *n),
+        },
+        JsonValue::Int(i) => write!(out, "{}", i),
+        JsonValue::RawNumber(raw) => out.write_str(raw),
+        JsonValue::String(s) => write_quoted_with(out, s, options.escape_non_ascii),
+        JsonValue::RawJson(raw) => out.write_str(raw),
+        JsonValue::Array(arr) if arr.is_empty() => out.write_str("[]"),
+        JsonValue::Obj(map) if map.is_empty() => out.write_str("{}"),
+        JsonValue::Array(_) | JsonValue::Obj(_)
+            if options.max_depth.is_some_and(|max| depth >= max) =>
+        {
+            out.write_str("\"...\"")
+        }
+        JsonValue::Array(_) | JsonValue::Obj(_)
+            if let Some(inline) = inline_form(out, value, options, depth) =>
+        {
+            out.write_str(&inline)
+        }
+        JsonValue::Array(arr) => {
+            out.push('[');
+            for (i, item) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, depth + 1);
+                write_value(out, item, options, depth + 1)?;
+            }
+            newline(out, depth);
+            out.write_char(']')
+        }
+        JsonValue::Obj(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            if options.sort_keys {
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+            }
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, depth + 1);
+                write_quoted_with(out, key, options.escape_non_ascii)?;
+                out.push_str(if options.indent.is_some() { ": " } else { ":" });
+                write_value(out, item, options, depth + 1)?;
+            }
+            newline(out, depth);
+            out.write_char('}')
+        }
+    }
+}
+
+/// The one-line form of `value`, if it ends within `max_width` columns of
+/// the line `out` ends with. Gives up as soon as it runs past, so measuring
+/// costs about the width rather than the size of the subtree.
+fn inline_form(
+    out: &str,
+    value: &JsonValue,
+    options: &SerializeOptions,
+    depth: usize,
+) -> Option<String> {
+    let (Some(_), Some(max_width)) = (options.indent, options.max_width) else {
+        return None;
+    };
+    let column = out[out.rfind('\n').map_or(0, |i| i + 1)..].chars().count();
+    let room = max_width.checked_sub(column)?;
+    let mut inline = String::new();
+    // A character takes at most 4 bytes
+    write_inline(&mut inline, value, options, depth, room.saturating_mul(4)).ok()?;
+    (inline.chars().count() <= room).then_some(inline)
+}
+
+/// One-line form used by `max_width`, with a space after each `,` and `:`.
+/// Fails once `out` grows past `limit` bytes.
+fn write_inline(
+    out: &mut String,
+    value: &JsonValue,
+    options: &SerializeOptions,
+    depth: usize,
+    limit: usize,
+) -> std::fmt::Result {
+    let truncated = options.max_depth.is_some_and(|max| depth >= max);
+    match value {
+        JsonValue::Array(arr) if !arr.is_empty() && !truncated => {
+            out.push('[');
+            for (i, item) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_inline(out, item, options, depth + 1, limit)?;
+            }
+            out.push(']');
+        }
+        JsonValue::Obj(map) if !map.is_empty() && !truncated => {
+            let mut entries: Vec<_> = map.iter().collect();
+            if options.sort_keys {
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+            }
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_quoted_with(out, key, options.escape_non_ascii)?;
+                out.push_str(": ");
+                write_inline(out, item, options, depth + 1, limit)?;
+            }
+            out.push('}');
+        }
+        // Scalars, empty containers and the `"..."` of truncated ones
+        _ => write_value(out, value, options, depth)?,
+    }
+    if out.len() > limit {
+        return Err(std::fmt::Error);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    fn sample() -> JsonValue {
+        from_str(r#"{"b": [1, "é"], "a": {}}"#).unwrap()
+    }
+
+    #[test]
+    fn test_default_is_compact() {
+        let value = sample();
+        assert_eq!(
+            value.to_string_with_options(&SerializeOptions::default()),
+            value.to_string()
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let value =
+            from_str(r#"{"a": {"b": {"c": [1]}, "d": []}, "e": [1, [2, [3]]], "f": 4}"#).unwrap();
+        let truncated = |max_depth| {
+            value.to_string_with_options(&SerializeOptions {
+                max_depth: Some(max_depth),
+                ..Default::default()
+            })
+        };
+        assert_eq!(
+            truncated(2),
+            r#"{"a":{"b":"...","d":[]},"e":[1,"..."],"f":4}"#
+        );
+        assert_eq!(truncated(0), r#""...""#);
+        assert_eq!(truncated(4), value.to_string());
+    }
+
+    #[test]
+    fn test_max_width() {
+        let value = from_str(
+            r#"{"short": [1, 2, 3], "long": ["alpha", "beta", "gamma", "delta"], "nested": {"a": [], "b": {"c": true}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value.to_pretty_string_width(40),
+            r#"{
+  "short": [1, 2, 3],
+  "long": [
+    "alpha",
+    "beta",
+    "gamma",
+    "delta"
+  ],
+  "nested": {"a": [], "b": {"c": true}}
+}"#
+        );
+        assert_eq!(value.to_pretty_string_width(0), value.to_pretty_string());
+
+        let deep = from_str(r#"{"a": {"b": {"c": [1, 2, 3, 4, 5, 6, 7, 8]}}, "d": [1, [2, [3]]]}"#)
+            .unwrap();
+        let options = SerializeOptions {
+            indent: Some(2),
+            max_width: Some(30),
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            deep.to_string_with_options(&options),
+            "{\n  \"a\": {\"b\": \"...\"},\n  \"d\": [1, \"...\"]\n}"
+        );
+
+        assert_eq!(
+            from_str(r#"{"a": [1, 2]}"#)
+                .unwrap()
+                .to_pretty_string_width(13),
+            r#"{"a": [1, 2]}"#
+        );
+    }
+
+    #[test]
+    fn test_float_format() {
+        let fixed = |value: &JsonValue, digits| {
+            value.to_string_with_options(&SerializeOptions {
+                float_format: FloatFormat::Fixed(digits),
+                ..Default::default()
+            })
+        };
+        assert_eq!(fixed(&JsonValue::Number(1.5), 3), "1.500");
+        assert_eq!(
+            fixed(&from_str(r#"{"a": [2, 0.125, -3.14159]}"#).unwrap(), 2),
+            r#"{"a":[2.00,0.12,-3.14]}"#
+        );
+        assert_eq!(fixed(&JsonValue::Number(2.5), 0), "2");
+        assert_eq!(fixed(&JsonValue::Int(7), 2), "7");
+        assert_eq!(fixed(&JsonValue::Number(f64::NAN), 2), "NaN");
+        assert_eq!(
+            JsonValue::Number(0.1 + 0.2).to_string_with_options(&SerializeOptions::default()),
+            "0.30000000000000004"
+        );
+    }
+
+    #[test]
+    fn test_serialized_len() {
+        let values = [
+            sample(),
+            JsonValue::Null,
+            JsonValue::Number(-1.25e-7),
+            JsonValue::Int(i64::MIN),
+            from_str(r#""quote \" backslash \\ tab \t nul \u0000 é 😀""#).unwrap(),
+            from_str(r#"{"a\nb": [true, false, null, {}, []], "": 1e300}"#).unwrap(),
+        ];
+        for value in values {
+            assert_eq!(value.serialized_len(), value.to_string().len(), "{}", value);
+        }
+        assert_eq!(JsonValue::String("\u{1}".to_string()).serialized_len(), 8);
+    }
+
+    #[test]
+    fn test_to_json_lines() {
+        let value = from_str(r#"[{"a": [1, 2]}, "two\nlines", null]"#).unwrap();
+        let lines = value.to_json_lines().unwrap();
+        assert_eq!(lines, "{\"a\":[1,2]}\n\"two\\nlines\"\nnull\n");
+
+        let read: Result<Vec<JsonValue>, _> =
+            crate::from_reader_lines(std::io::Cursor::new(lines)).collect();
+        assert_eq!(read.map(JsonValue::Array), Ok(value));
+
+        assert_eq!(
+            JsonValue::Array(vec![]).to_json_lines(),
+            Some(String::new())
+        );
+        assert_eq!(from_str("{}").unwrap().to_json_lines(), None);
+
+        let raw = JsonValue::Array(vec![
+            JsonValue::Null,
+            JsonValue::Array(vec![JsonValue::RawJson("[1,\n2]".to_string())]),
+        ]);
+        assert_eq!(raw.to_json_lines(), None);
+        let raw = JsonValue::Array(vec![JsonValue::RawJson("[1, 2]".to_string())]);
+        assert_eq!(raw.to_json_lines(), Some("[1, 2]\n".to_string()));
+    }
+
+    #[test]
+    fn test_indent() {
+        let options = SerializeOptions {
+            indent: Some(4),
+            ..Default::default()
+        };
+        assert_eq!(
+            sample().to_string_with_options(&options),
+            "{\n    \"b\": [\n        1,\n        \"é\"\n    ],\n    \"a\": {}\n}"
+        );
+    }
+
+    #[test]
+    fn test_crlf() {
+        let options = SerializeOptions {
+            indent: Some(2),
+            trailing_newline: true,
+            line_ending: LineEnding::CrLf,
+            ..Default::default()
+        };
+        let text = sample().to_string_with_options(&options);
+        assert_eq!(
+            text,
+            "{\r\n  \"b\": [\r\n    1,\r\n    \"é\"\r\n  ],\r\n  \"a\": {}\r\n}\r\n"
+        );
+        assert_eq!(text.matches('\n').count(), text.matches("\r\n").count());
+        assert_eq!(from_str(&text), Ok(sample()));
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let options = SerializeOptions {
+            sort_keys: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            sample().to_string_with_options(&options),
+            r#"{"a":{},"b":[1,"é"]}"#
+        );
+    }
+
+    #[test]
+    fn test_escape_non_ascii() {
+        let options = SerializeOptions {
+            escape_non_ascii: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            sample().to_string_with_options(&options),
+            r#"{"b":[1,"\u00e9"],"a":{}}"#
+        );
+    }
+
+    #[test]
+    fn test_escape_non_ascii_round_trip() {
+        let options = SerializeOptions {
+            escape_non_ascii: true,
+            ..Default::default()
+        };
+        let value = JsonValue::String("café 😀!".to_string());
+        let text = value.to_string_with_options(&options);
+        assert_eq!(text, r#""caf\u00e9 \ud83d\ude00!""#);
+        assert!(text.is_ascii());
+        assert_eq!(from_str(&text), Ok(value));
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        let options = SerializeOptions {
+            trailing_newline: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            sample().to_string_with_options(&options),
+            "{\"b\":[1,\"é\"],\"a\":{}}\n"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt;
+
+use crate::pointer::escape_token;
+use crate::{JsonValue, TypeError};
+
+/// A simple description of the structure a value is expected to have.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Any,
+    Null,
+    Bool,
+    Number,
+    String,
+    /// An array whose elements all have the inner shape.
+    Array(Box<Shape>),
+    /// An object with at least these keys. Other keys are allowed.
+    Object(Vec<(String, Shape)>),
+}
+
+impl Shape {
+    /// Shorthand for `Shape::Object` from borrowed keys.
+    pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, Shape)>) -> Shape {
+        Shape::Object(
+            fields
+                .into_iter()
+                .map(|(key, shape)| (key.to_string(), shape))
+                .collect(),
+        )
+    }
+
+    pub fn array(element: Shape) -> Shape {
+        Shape::Array(Box::new(element))
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Shape::Any => "any",
+            Shape::Null => "null",
+            Shape::Bool => "boolean",
+            Shape::Number => "number",
+            Shape::String => "string",
+            Shape::Array(_) => "array",
+            Shape::Object(_) => "object",
+        }
+    }
+}
+
+/// A place where a value doesn't match its shape. Paths are JSON Pointers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShapeError {
+    MissingKey { path: String },
+    WrongType { path: String, error: TypeError },
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (path, problem) = match self {
+            ShapeError::MissingKey { path } => (path, "missing".to_string()),
+            ShapeError::WrongType { path, error } => (path, error.to_string()),
+        };
+        let path = if path.is_empty() { "root" } else { path };
+        write!(f, "{}: {}", path, problem)
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+impl JsonValue {
+    /// Checks the value against `shape`, reporting every mismatch rather
+    /// than only the first.
+    pub fn matches_shape(&self, shape: &Shape) -> Result<(), Vec<ShapeError>> {
+        let mut errors = vec![];
+        check(self, shape, &mut String::new(), &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn check(value: &JsonValue, shape: &Shape, path: &mut String, errors: &mut Vec<ShapeError>) {
+    match (shape, value) {
+        (Shape::Any, _)
+        | (Shape::Null, JsonValue::Null)
+        | (Shape::Bool, JsonValue::Bool(_))
+        | (Shape::Number, JsonValue::Number(_) | JsonValue::Int(_) | JsonValue::RawNumber(_))
+        | (Shape::String, JsonValue::String(_)) => {}
+        (Shape::Array(element), JsonValue::Array(arr)) => {
+            for (i, item) in arr.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/{}", i));
+                check(item, element, path, errors);
+                path.truncate(len);
+            }
+        }
+        (Shape::Object(fields), JsonValue::Obj(map)) => {
+            for (key, field) in fields {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&escape_token(key));
+                match map.get(key) {
+                    Some(item) => check(item, field, path, errors),
+                    None => errors.push(ShapeError::MissingKey { path: path.clone() }),
+                }
+                path.truncate(len);
+            }
+        }
+        _ => errors.push(ShapeError::WrongType {
+            path: path.clone(),
+            error: TypeError::new(shape.type_name(), value),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_matches_shape() {
+        let shape = Shape::object([
+            ("id", Shape::Number),
+            ("name", Shape::String),
+            ("tags", Shape::array(Shape::String)),
+        ]);
+        let value = from_str(r#"{"id": 1, "name": "ann", "tags": ["a"], "extra": null}"#).unwrap();
+        assert_eq!(value.matches_shape(&shape), Ok(()));
+
+        let value = from_str(r#"{"id": "1", "tags": ["a", 2]}"#).unwrap();
+        let errors = value.matches_shape(&shape).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ShapeError::WrongType {
+                    path: "/id".to_string(),
+                    error: TypeError {
+                        expected: "number",
+                        found: "string"
+                    }
+                },
+                ShapeError::MissingKey {
+                    path: "/name".to_string()
+                },
+                ShapeError::WrongType {
+                    path: "/tags/1".to_string(),
+                    error: TypeError {
+                        expected: "string",
+                        found: "number"
+                    }
+                },
+            ]
+        );
+        assert_eq!(errors[0].to_string(), "/id: expected number, found string");
+
+        let errors = JsonValue::Null.matches_shape(&shape).unwrap_err();
+        assert_eq!(errors[0].to_string(), "root: expected object, found null");
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+
+use crate::JsonValue;
+use crate::escape::write_quoted;
+use crate::value::write_number;
+
+/// A `JsonValue` whose strings and containers are reference counted, so
+/// cloning it (or any subtree) is a pointer copy instead of a deep copy.
+/// The shared data is immutable; convert back with `to_json_value` to edit.
+///
+/// Being immutable, a container can only hold values built before it, so
+/// no value can contain itself and serializing always terminates. There is
+/// no cycle to detect.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedJsonValue {
+    Null,
+    Number(f64),
+    Int(i64),
+    RawNumber(Arc<str>),
+    String(Arc<str>),
+    Bool(bool),
+    Array(Arc<[SharedJsonValue]>),
+    Obj(Arc<IndexMap<String, SharedJsonValue>>),
+    RawJson(Arc<str>),
+}
+
+impl JsonValue {
+    pub fn into_shared(self) -> SharedJsonValue {
+        self.share(None)
+    }
+
+    /// Like `into_shared`, but equal string values share one allocation,
+    /// which saves memory on documents that repeat the same strings (enum-like
+    /// fields, tags) many times, for as long as the shared tree is kept.
+    ///
+    /// It runs after parsing, so it doesn't lower peak memory: the owned tree
+    /// still holds one `String` per value until it is consumed here. The
+    /// parser can't deduplicate as it goes, since every `JsonValue::String`
+    /// owns its text.
+    pub fn into_shared_interned(self) -> SharedJsonValue {
+        self.share(Some(&mut HashSet::new()))
+    }
+
+    fn share(self, mut strings: Option<&mut HashSet<Arc<str>>>) -> SharedJsonValue {
+        match self {
+            JsonValue::Null => SharedJsonValue::Null,
+            JsonValue::Number(n) => SharedJsonValue::Number(n),
+            JsonValue::Int(i) => SharedJsonValue::Int(i),
+            JsonValue::RawNumber(raw) => SharedJsonValue::RawNumber(raw.into()),
+            JsonValue::String(s) => SharedJsonValue::String(match strings {
+                Some(strings) => match strings.get(s.as_str()) {
+                    Some(shared) => shared.clone(),
+                    None => {
+                        let shared: Arc<str> = s.into();
+                        strings.insert(shared.clone());
+                        shared
+                    }
+                },
+                None => s.into(),
+            }),
+            JsonValue::RawJson(raw) => SharedJsonValue::RawJson(raw.into()),
+            JsonValue::Bool(b) => SharedJsonValue::Bool(b),
+            JsonValue::Array(arr) => SharedJsonValue::Array(
+                arr.into_iter()
+                    .map(|v| v.share(strings.as_deref_mut()))
+                    .collect(),
+            ),
+            JsonValue::Obj(map) => SharedJsonValue::Obj(Arc::new(
+                map.into_iter()
+                    .map(|(k, v)| (k, v.share(strings.as_deref_mut())))
+                    .collect(),
+            )),
+        }
+    }
+}
+
+impl SharedJsonValue {
+    pub fn get(&self, key: &str) -> Option<&SharedJsonValue> {
+        match self {
+            SharedJsonValue::Obj(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Deep copies the value back into an owned `JsonValue`.
+    pub fn to_json_value(&self) -> JsonValue {
+        match self {
+            SharedJsonValue::Null => JsonValue::Null,
+            SharedJsonValue::Number(n) => JsonValue::Number(*n),
+            SharedJsonValue::Int(i) => JsonValue::Int(*i),
+            SharedJsonValue::RawNumber(raw) => JsonValue::RawNumber(raw.to_string()),
+            SharedJsonValue::String(s) => JsonValue::String(s.to_string()),
+            SharedJsonValue::RawJson(raw) => JsonValue::RawJson(raw.to_string()),
+            SharedJsonValue::Bool(b) => JsonValue::Bool(*b),
+            SharedJsonValue::Array(arr) => {
+                JsonValue::Array(arr.iter().map(SharedJsonValue::to_json_value).collect())
+            }
+            SharedJsonValue::Obj(map) => JsonValue::Obj(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), v.to_json_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for SharedJsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SharedJsonValue::Null => write!(f, "null"),
+            SharedJsonValue::Bool(b) => write!(f, "{}", b),
+            SharedJsonValue::Number(n) => write_number(f, *n),
+            SharedJsonValue::Int(i) => write!(f, "{}", i),
+            SharedJsonValue::RawNumber(raw) => f.write_str(raw),
+            SharedJsonValue::String(s) => write_quoted(f, s),
+            SharedJsonValue::RawJson(raw) => f.write_str(raw),
+            SharedJsonValue::Array(arr) => {
+                write!(f, "[")?;
+                for (i, item) in arr.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            SharedJsonValue::Obj(map) => {
+                write!(f, "{{")?;
+                let mut first = true;
+                for (key, value) in map.iter() {
+                    if !first {
+                        write!(f, ",")?;
+                    }
+                    write_quoted(f, key)?;
+                    write!(f, ":{}", value)?;
+                    first = false;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_shared_clone_is_cheap() {
+        let input = format!(
+            r#"{{"items": [{}], "name": "todos"}}"#,
+            (0..10_000)
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        let value = from_str(&input).unwrap();
+        let shared = value.clone().into_shared();
+
+        let copy = shared.clone();
+        match (shared.get("items"), copy.get("items")) {
+            (Some(SharedJsonValue::Array(a)), Some(SharedJsonValue::Array(b))) => {
+                assert!(Arc::ptr_eq(a, b));
+                assert_eq!(a.len(), 10_000);
+            }
+            _ => panic!("Expected arrays"),
+        }
+        assert_eq!(copy.to_json_value(), value);
+        assert_eq!(copy.to_string(), value.to_string());
+    }
+
+    #[test]
+    fn test_into_shared_interned() {
+        let input = format!(
+            "[{}]",
+            (0..1_000)
+                .map(|i| format!(
+                    r#"{{"status": "{}", "id": {}}}"#,
+                    ["open", "closed"][i % 2],
+                    i
+                ))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        let value = from_str(&input).unwrap();
+        let allocations = |shared: &SharedJsonValue| {
+            let SharedJsonValue::Array(items) = shared else {
+                panic!("Expected an array");
+            };
+            items
+                .iter()
+                .map(|item| match item.get("status") {
+                    Some(SharedJsonValue::String(s)) => s.as_ptr(),
+                    _ => panic!("Expected a string"),
+                })
+                .collect::<HashSet<_>>()
+                .len()
+        };
+
+        let interned = value.clone().into_shared_interned();
+        assert_eq!(allocations(&interned), 2);
+        assert_eq!(allocations(&value.clone().into_shared()), 1_000);
+        assert_eq!(interned.to_json_value(), value);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::JsonValue;
+
+/// Counts gathered by `JsonValue::stats`, for getting a feel of a payload.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub nulls: usize,
+    pub bools: usize,
+    pub numbers: usize,
+    pub strings: usize,
+    pub raw: usize,
+    pub arrays: usize,
+    pub objects: usize,
+    /// Same counting as `JsonValue::depth`.
+    pub max_depth: usize,
+    /// UTF-8 length of every string value, keys not included.
+    pub string_bytes: usize,
+    /// UTF-8 length of every object key.
+    pub key_bytes: usize,
+}
+
+impl JsonValue {
+    /// Walks the tree once, counting nodes by type.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        self.collect_stats(&mut stats, 0);
+        stats
+    }
+
+    fn collect_stats(&self, stats: &mut Stats, depth: usize) {
+        match self {
+            JsonValue::Null => stats.nulls += 1,
+            JsonValue::Bool(_) => stats.bools += 1,
+            JsonValue::Number(_) | JsonValue::Int(_) | JsonValue::RawNumber(_) => {
+                stats.numbers += 1
+            }
+            JsonValue::String(s) => {
+                stats.strings += 1;
+                stats.string_bytes += s.len();
+            }
+            JsonValue::RawJson(_) => stats.raw += 1,
+            JsonValue::Array(arr) => {
+                stats.arrays += 1;
+                stats.max_depth = stats.max_depth.max(depth + 1);
+                for value in arr {
+                    value.collect_stats(stats, depth + 1);
+                }
+            }
+            JsonValue::Obj(map) => {
+                stats.objects += 1;
+                stats.max_depth = stats.max_depth.max(depth + 1);
+                for (key, value) in map {
+                    stats.key_bytes += key.len();
+                    value.collect_stats(stats, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_stats() {
+        let value = from_str(
+            r#"{"name": "café", "tags": ["a", "bc"], "n": [1, 2.5, {"ok": true, "x": null}], "e": {}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value.stats(),
+            Stats {
+                nulls: 1,
+                bools: 1,
+                numbers: 2,
+                strings: 3,
+                raw: 0,
+                arrays: 2,
+                objects: 3,
+                max_depth: 3,
+                string_bytes: 8,
+                key_bytes: 13,
+            }
+        );
+        assert_eq!(value.stats().max_depth, value.depth());
+        assert_eq!(JsonValue::Null.stats().max_depth, 0);
+    }
+}
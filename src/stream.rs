@@ -0,0 +1,121 @@
+use crate::{JsonValue, ParseError, Validator, from_str};
+
+/// Parses a document that arrives in pieces, e.g. from a socket.
+///
+/// Chunks can be cut anywhere, even inside a string or a multi-byte
+/// character. Each one is checked as it comes in, so malformed input fails
+/// on the `feed` that brings the mistake rather than at the end. The tree is
+/// built by `finish`, accepting the same grammar as `from_str`.
+#[derive(Debug, Default)]
+pub struct StreamingParser {
+    buffer: Vec<u8>,
+    /// Length of the prefix of `buffer` already given to the validator.
+    checked: usize,
+    validator: Validator,
+    error: Option<ParseError>,
+}
+
+impl StreamingParser {
+    pub fn new() -> StreamingParser {
+        StreamingParser::default()
+    }
+
+    /// Adds the next chunk of the document. Once it has failed, every later
+    /// call returns the same error.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        if let Some(e) = &self.error {
+            return Err(e.clone());
+        }
+        self.buffer.extend_from_slice(bytes);
+        let result = self.check();
+        if let Err(e) = &result {
+            self.error = Some(e.clone());
+        }
+        result
+    }
+
+    /// Checks the complete characters fed so far, keeping the start of a
+    /// character cut by the chunk boundary for the next call.
+    fn check(&mut self) -> Result<(), ParseError> {
+        let pending = &self.buffer[self.checked..];
+        let valid = match std::str::from_utf8(pending) {
+            Ok(text) => text,
+            Err(e) if e.error_len().is_some() => {
+                return Err(ParseError::InvalidUtf8 {
+                    offset: self.checked + e.valid_up_to(),
+                });
+            }
+            Err(e) => std::str::from_utf8(&pending[..e.valid_up_to()]).unwrap(),
+        };
+        self.validator.feed(valid)?;
+        self.checked += valid.len();
+        Ok(())
+    }
+
+    /// Ends the input and builds the value.
+    pub fn finish(mut self) -> Result<JsonValue, ParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        if self.checked < self.buffer.len() {
+            return Err(ParseError::InvalidUtf8 {
+                offset: self.checked,
+            });
+        }
+        self.validator.finish()?;
+        let input = std::str::from_utf8(&self.buffer).unwrap();
+        from_str(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed_in_pieces() {
+        let input =
+            r#"{"name": "café \"au\" lait", "sizes": [1, 2.5e3, -0], "hot": true, "x": "😀"}"#
+                .as_bytes();
+        let expected = crate::from_slice(input).unwrap();
+        for first in 0..input.len() {
+            for second in first..input.len() {
+                let mut parser = StreamingParser::new();
+                parser.feed(&input[..first]).unwrap();
+                parser.feed(&input[first..second]).unwrap();
+                parser.feed(&input[second..]).unwrap();
+                assert_eq!(
+                    parser.finish(),
+                    Ok(expected.clone()),
+                    "{} {}",
+                    first,
+                    second
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut parser = StreamingParser::new();
+        assert_eq!(parser.feed(b"[1, "), Ok(()));
+        let error = ParseError::UnexpectedChar { ch: '}', pos: 4 };
+        assert_eq!(parser.feed(b"}, 2]"), Err(error.clone()));
+        assert_eq!(parser.feed(b"3]"), Err(error.clone()));
+        assert_eq!(parser.finish(), Err(error));
+
+        let mut parser = StreamingParser::new();
+        parser.feed(b"{\"a\": ").unwrap();
+        assert_eq!(parser.finish(), Err(ParseError::UnexpectedEof));
+
+        let mut parser = StreamingParser::new();
+        parser.feed(b"\"\xc3").unwrap();
+        assert_eq!(parser.finish(), Err(ParseError::InvalidUtf8 { offset: 1 }));
+
+        let mut parser = StreamingParser::new();
+        assert_eq!(
+            parser.feed(b"\"\xc3("),
+            Err(ParseError::InvalidUtf8 { offset: 1 })
+        );
+    }
+}
@@ -0,0 +1,623 @@
+use crate::escape::decode_escape;
+use crate::{CommentStyle, ParseError, ParserOptions};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Colon,
+    Comma,
+    String(String),
+    Number(f64),
+    True,
+    False,
+    Null,
+}
+
+impl Token {
+    /// The character a punctuation token is written as.
+    pub(crate) fn delimiter(&self) -> Option<char> {
+        match self {
+            Token::LBrace => Some('{'),
+            Token::RBrace => Some('}'),
+            Token::LBracket => Some('['),
+            Token::RBracket => Some(']'),
+            Token::Colon => Some(':'),
+            Token::Comma => Some(','),
+            _ => None,
+        }
+    }
+}
+
+/// Byte range `start..end` of a token in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits JSON text into tokens, skipping the whitespace between them.
+#[derive(Debug)]
+pub struct Tokenizer {
+    src: String,
+    pos: usize,
+    options: ParserOptions,
+}
+
+impl Tokenizer {
+    pub fn new(src: String) -> Tokenizer {
+        Tokenizer::with_options(src, ParserOptions::default())
+    }
+
+    pub fn with_options(src: String, options: ParserOptions) -> Tokenizer {
+        let mut tokenizer = Tokenizer {
+            src,
+            pos: 0,
+            options,
+        };
+        if tokenizer.options.shebang && tokenizer.src.starts_with("#!") {
+            tokenizer.skip_line();
+        }
+        tokenizer
+    }
+
+    pub fn source(&self) -> &str {
+        &self.src
+    }
+
+    pub(crate) fn into_source(self) -> String {
+        self.src
+    }
+
+    /// Byte offset of the next unread character.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the next token, or `None` once only whitespace is left.
+    pub fn next_token(&mut self) -> Result<Option<(Token, Span)>, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let token = match self.peek() {
+            None => return Ok(None),
+            Some('"') => self.lex_string()?,
+            Some('\'') if self.options.single_quotes => self.lex_string()?,
+            Some('0'..='9' | '-') => self.lex_number()?,
+            Some('+') if self.options.leading_plus => self.lex_number()?,
+            Some('+' | '.') if self.options.number_hints => return Err(self.number_hint()),
+            Some('t' | 'f' | 'n') => self.lex_literal()?,
+            Some('N' | 'I') if self.options.nan_infinity => self.lex_literal()?,
+            Some(ch) => {
+                let token = match ch {
+                    '{' => Token::LBrace,
+                    '}' => Token::RBrace,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    ':' => Token::Colon,
+                    ',' => Token::Comma,
+                    _ => return Err(self.unexpected()),
+                };
+                self.advance();
+                token
+            }
+        };
+        Ok(Some((
+            token,
+            Span {
+                start,
+                end: self.pos,
+            },
+        )))
+    }
+
+    /// Returns what `next_token` would, without moving past it. The token
+    /// is read again by the next call, so peeking is not free on long strings.
+    pub fn peek_token(&mut self) -> Result<Option<(Token, Span)>, ParseError> {
+        let pos = self.pos;
+        let token = self.next_token();
+        self.pos = pos;
+        token
+    }
+
+    /// Returns true if nothing but whitespace is left.
+    pub(crate) fn is_at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.peek().is_none()
+    }
+
+    /// Counts the elements of the container whose opening bracket was just
+    /// read, without tokenizing them. Strings and comments are skipped the
+    /// way the options say. Only a sizing hint: a trailing comma counts as one
+    /// extra element.
+    pub(crate) fn count_elements(&self) -> usize {
+        let src = &self.src.as_bytes()[self.pos..];
+        let comments = self.options.comments;
+        let mut depth = 0;
+        let mut count = 0;
+        let mut empty = true;
+        let mut i = 0;
+        while i < src.len() {
+            let rest = &src[i..];
+            match src[i] {
+                b'[' | b'{' => depth += 1,
+                b']' | b'}' if depth == 0 => break,
+                b']' | b'}' => depth -= 1,
+                b',' if depth == 0 => count += 1,
+                quote @ (b'"' | b'\'') if quote == b'"' || self.options.single_quotes => {
+                    i += 1;
+                    while i < src.len() && src[i] != quote {
+                        i += if src[i] == b'\\' { 2 } else { 1 };
+                    }
+                }
+                b'/' if comments.contains(CommentStyle::LINE) && rest.starts_with(b"//") => {
+                    i += rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+                    continue;
+                }
+                b'#' if comments.contains(CommentStyle::HASH) => {
+                    i += rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+                    continue;
+                }
+                b'/' if comments.contains(CommentStyle::BLOCK) && rest.starts_with(b"/*") => {
+                    i += match rest[2..].windows(2).position(|w| w == b"*/") {
+                        Some(end) => 2 + end + 2,
+                        None => rest.len(),
+                    };
+                    continue;
+                }
+                b'\t' | b'\n' | b' ' | b'\r' => {
+                    i += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            empty = false;
+            i += 1;
+        }
+        if empty { 0 } else { count + 1 }
+    }
+
+    /// Steps over one character, used to get past input that can't be lexed.
+    pub(crate) fn skip_char(&mut self) {
+        self.advance();
+    }
+
+    fn consume(&mut self, to_match: char) -> bool {
+        if self.peek() == Some(to_match) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Error for whatever sits at the current position.
+    fn unexpected(&self) -> ParseError {
+        match self.peek() {
+            Some(ch) => ParseError::UnexpectedChar { ch, pos: self.pos },
+            None => ParseError::UnexpectedEof,
+        }
+    }
+
+    /// Reads a string delimited by whichever quote it starts with.
+    fn lex_string(&mut self) -> Result<Token, ParseError> {
+        let quote = match self.advance() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => return Err(self.unexpected()),
+        };
+        let mut result = String::new();
+        loop {
+            match self.peek() {
+                Some(ch) if ch == quote => {
+                    self.advance();
+                    return Ok(Token::String(result));
+                }
+                Some('\\') if quote == '\'' && self.src[self.pos..].starts_with("\\'") => {
+                    result.push('\'');
+                    self.pos += 2;
+                }
+
+                Some('\\') => {
+                    let (ch, len) = decode_escape(&self.src[self.pos + 1..])
+                        .ok_or(ParseError::InvalidEscape { pos: self.pos })?;
+                    result.push(ch);
+                    self.pos += 1 + len;
+                }
+                // A NUL has to be written as `\u0000`
+                Some('\0') => return Err(self.unexpected()),
+                Some('\u{1}'..='\u{1f}') if !self.options.control_characters => {
+                    return Err(self.unexpected());
+                }
+                Some(ch) => {
+                    result.push(ch);
+                    self.advance();
+                }
+                None => return Err(ParseError::UnexpectedEof),
+            }
+        }
+    }
+
+    /// Consumes `word` if the input continues with it, which must end there:
+    /// `nullable` is not `null` followed by `able`.
+    fn consume_keyword(&mut self, word: &str) -> Result<bool, ParseError> {
+        if !self.src[self.pos..].starts_with(word) {
+            return Ok(false);
+        }
+        let end = self.pos + word.len();
+        match self.src[end..].chars().next() {
+            Some(ch) if ch.is_alphanumeric() || ch == '_' => {
+                Err(ParseError::UnexpectedChar { ch, pos: end })
+            }
+            _ => {
+                self.pos = end;
+                Ok(true)
+            }
+        }
+    }
+
+    fn lex_literal(&mut self) -> Result<Token, ParseError> {
+        if self.consume_keyword("true")? {
+            Ok(Token::True)
+        } else if self.consume_keyword("false")? {
+            Ok(Token::False)
+        } else if self.consume_keyword("null")? {
+            Ok(Token::Null)
+        } else if self.options.nan_infinity && self.consume_keyword("NaN")? {
+            Ok(Token::Number(f64::NAN))
+        } else if self.options.nan_infinity && self.consume_keyword("Infinity")? {
+            Ok(Token::Number(f64::INFINITY))
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// `-? digits (. digits)? ([eE] [+-]? digits)?`, where `+` can take the
+    /// place of `-` if `leading_plus` is on.
+    ///
+    /// Zero keeps its sign: `-0` and `-0.0` become `-0.0`, which compares
+    /// equal to `0.0` but stays distinguishable through `is_sign_negative`
+    /// and is written back as `-0`. Leading zeros are accepted unless
+    /// `leading_zeros` is off, `007` is 7.
+    ///
+    /// The decimal separator is `.` whatever the options or locale. A comma
+    /// always ends the number, so `[1,5]` is two elements and never `1.5`.
+    fn lex_number(&mut self) -> Result<Token, ParseError> {
+        let idx = self.pos;
+        let sign = if self.consume('-') {
+            Some(f64::NEG_INFINITY)
+        } else if self.options.leading_plus && self.consume('+') {
+            Some(f64::INFINITY)
+        } else {
+            None
+        };
+        if let Some(infinity) = sign
+            && self.options.nan_infinity
+            && self.consume_keyword("Infinity")?
+        {
+            return Ok(Token::Number(infinity));
+        }
+        let digits = self.pos;
+        self.expect_digits()?;
+        if !self.options.leading_zeros
+            && self.pos - digits > 1
+            && self.src[digits..].starts_with('0')
+        {
+            return Err(ParseError::UnexpectedChar {
+                ch: self.src[digits + 1..].chars().next().unwrap(),
+                pos: digits + 1,
+            });
+        }
+        if self.consume('.') {
+            self.expect_digits()?;
+        }
+        if self.consume('e') || self.consume('E') {
+            if !self.consume('+') {
+                self.consume('-');
+            }
+            self.expect_digits()?;
+        }
+
+        let value = number_value(&self.src[idx..self.pos], idx)?;
+        if self.options.reject_out_of_range && value.is_infinite() {
+            return Err(ParseError::NumberOutOfRange { pos: idx });
+        }
+        Ok(Token::Number(value))
+    }
+
+    /// Error for a number starting with `+` or `.`, under `number_hints`.
+    /// The hint is only given when following it makes a valid number, so
+    /// `.5e` or `+.5` are still unexpected characters.
+    fn number_hint(&self) -> ParseError {
+        let rest = &self.src[self.pos + 1..];
+        let len = rest
+            .find(|ch: char| !(ch.is_ascii_digit() || matches!(ch, '.' | 'e' | 'E' | '+' | '-')))
+            .unwrap_or(rest.len());
+        let number = &rest[..len];
+        let plus = self.peek() == Some('+');
+        let fixed = if plus {
+            number.to_string()
+        } else {
+            format!("0.{}", number)
+        };
+        let mut check = Tokenizer::with_options(
+            fixed,
+            ParserOptions {
+                leading_zeros: self.options.leading_zeros,
+                ..Default::default()
+            },
+        );
+        if !number.starts_with(|ch: char| ch.is_ascii_digit())
+            || check.lex_number().is_err()
+            || check.pos != check.src.len()
+        {
+            return self.unexpected();
+        }
+        if plus {
+            ParseError::LeadingPlus { pos: self.pos }
+        } else {
+            ParseError::MissingLeadingZero {
+                fixed: check.src,
+                pos: self.pos,
+            }
+        }
+    }
+
+    fn expect_digits(&mut self) -> Result<(), ParseError> {
+        let idx = self.pos;
+        while matches!(self.peek(), Some(ch) if ch.is_ascii_digit()) {
+            self.advance();
+        }
+        if idx == self.pos {
+            Err(self.unexpected())
+        } else {
+            Ok(())
+        }
+    }
+
+    //TODO: Consider &str instead of String
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    /// Skips whitespace, and comments of the enabled styles.
+    fn skip_whitespace(&mut self) {
+        let comments = self.options.comments;
+        while let Some(ch) = self.peek() {
+            let rest = &self.src[self.pos..];
+            match ch {
+                '\t' | '\n' | ' ' | '\r' => {
+                    self.advance();
+                }
+                '/' if comments.contains(CommentStyle::LINE) && rest.starts_with("//") => {
+                    self.skip_line();
+                }
+                '#' if comments.contains(CommentStyle::HASH) => self.skip_line(),
+                '/' if comments.contains(CommentStyle::BLOCK) && rest.starts_with("/*") => {
+                    // An unclosed comment runs to the end of the input
+                    self.pos = match rest[2..].find("*/") {
+                        Some(end) => self.pos + 2 + end + 2,
+                        None => self.src.len(),
+                    };
+                }
+                _ => break,
+            };
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(ch) = self.advance() {
+            if ch == '\n' {
+                break;
+            }
+        }
+    }
+}
+
+impl Iterator for Tokenizer {
+    type Item = Result<(Token, Span), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_token() {
+            Ok(token) => token.map(Ok),
+            Err(e) => {
+                // Nothing sensible follows an error, stop here
+                self.pos = self.src.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Reads the text of a number token that starts at `pos`.
+fn number_value(text: &str, pos: usize) -> Result<f64, ParseError> {
+    text.parse().map_err(|_| ParseError::InvalidNumber { pos })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skip_whitespaces() {
+        let mut tokenizer = Tokenizer::new("      \t\n ".to_string());
+        tokenizer.skip_whitespace();
+        assert!(tokenizer.peek().is_none());
+
+        let mut tokenizer = Tokenizer::new("      \t\n  a".to_string());
+        tokenizer.skip_whitespace();
+        assert!(matches!(tokenizer.peek(), Some(ch) if ch == 'a'));
+    }
+
+    #[test]
+    fn test_comment_styles() {
+        let input = "[1, # one\n 2 // two\n]";
+        let options = ParserOptions {
+            comments: CommentStyle::HASH,
+            ..Default::default()
+        };
+        let tokens: Vec<_> = Tokenizer::with_options(input.to_string(), options)
+            .map(|t| t.map(|(token, _)| token))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Ok(Token::LBracket),
+                Ok(Token::Number(1.0)),
+                Ok(Token::Comma),
+                Ok(Token::Number(2.0)),
+                Err(ParseError::UnexpectedChar { ch: '/', pos: 13 }),
+            ]
+        );
+
+        let options = ParserOptions {
+            comments: CommentStyle::LINE | CommentStyle::BLOCK,
+            ..Default::default()
+        };
+        let input = "/* head */ [1 /* a, b */, 2 // two\n]";
+        let tokens = Tokenizer::with_options(input.to_string(), options)
+            .map(|t| t.map(|(token, _)| token))
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(
+            tokens,
+            Ok(vec![
+                Token::LBracket,
+                Token::Number(1.0),
+                Token::Comma,
+                Token::Number(2.0),
+                Token::RBracket,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_token_sequence() {
+        let tokens = Tokenizer::new(r#"{"a":[1,true]}"#.to_string())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let span = |start, end| Span { start, end };
+        assert_eq!(
+            tokens,
+            vec![
+                (Token::LBrace, span(0, 1)),
+                (Token::String("a".to_string()), span(1, 4)),
+                (Token::Colon, span(4, 5)),
+                (Token::LBracket, span(5, 6)),
+                (Token::Number(1.0), span(6, 7)),
+                (Token::Comma, span(7, 8)),
+                (Token::True, span(8, 12)),
+                (Token::RBracket, span(12, 13)),
+                (Token::RBrace, span(13, 14)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_number_value() {
+        assert_eq!(number_value("-1.5e2", 3), Ok(-150.0));
+        for text in ["", "-", "1.2.3", "1e", "0x10", "1_000"] {
+            assert_eq!(
+                number_value(text, 3),
+                Err(ParseError::InvalidNumber { pos: 3 }),
+                "{:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_peek_token() {
+        let mut tokenizer = Tokenizer::new(" [true ;".to_string());
+        let bracket = Ok(Some((Token::LBracket, Span { start: 1, end: 2 })));
+        assert_eq!(tokenizer.peek_token(), bracket);
+        assert_eq!(tokenizer.peek_token(), bracket);
+        assert_eq!(tokenizer.position(), 0);
+        assert_eq!(tokenizer.next_token(), bracket);
+
+        let literal = Ok(Some((Token::True, Span { start: 2, end: 6 })));
+        assert_eq!(tokenizer.peek_token(), literal);
+        assert_eq!(tokenizer.next_token(), literal);
+
+        let error = Err(ParseError::UnexpectedChar { ch: ';', pos: 7 });
+        assert_eq!(tokenizer.peek_token(), error);
+        assert_eq!(tokenizer.next_token(), error);
+    }
+
+    #[test]
+    fn test_null_word_boundary() {
+        let mut tokenizer = Tokenizer::new("[null,nullable]".to_string());
+        assert_eq!(
+            tokenizer.nth(1).unwrap(),
+            Ok((Token::Null, Span { start: 1, end: 5 }))
+        );
+        assert_eq!(
+            tokenizer.nth(1),
+            Some(Err(ParseError::UnexpectedChar { ch: 'a', pos: 10 }))
+        );
+    }
+
+    #[test]
+    fn test_nan_infinity_word_boundary() {
+        let options = ParserOptions {
+            nan_infinity: true,
+            ..Default::default()
+        };
+        let tokens = |input: &str| {
+            Tokenizer::with_options(input.to_string(), options.clone())
+                .collect::<Result<Vec<_>, _>>()
+                .map(|tokens| tokens.into_iter().map(|(t, _)| t).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            tokens("NaNx"),
+            Err(ParseError::UnexpectedChar { ch: 'x', pos: 3 })
+        );
+        assert_eq!(
+            tokens("Infinityfoo"),
+            Err(ParseError::UnexpectedChar { ch: 'f', pos: 8 })
+        );
+        assert_eq!(
+            tokens("-Infinity_"),
+            Err(ParseError::UnexpectedChar { ch: '_', pos: 9 })
+        );
+        assert_eq!(tokens("[-Infinity,NaN]").map(|t| t.len()), Ok(5));
+    }
+
+    #[test]
+    fn test_nul_in_string() {
+        let mut tokenizer = Tokenizer::new(r#""a\u0000b""#.to_string());
+        assert_eq!(
+            tokenizer.next().unwrap().map(|(token, _)| token),
+            Ok(Token::String("a\0b".to_string()))
+        );
+
+        let mut tokenizer = Tokenizer::new("\"a\0b\"".to_string());
+        assert_eq!(
+            tokenizer.next(),
+            Some(Err(ParseError::UnexpectedChar { ch: '\0', pos: 2 }))
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_error() {
+        let mut tokenizer = Tokenizer::new("[1 ;".to_string());
+        assert_eq!(
+            tokenizer.next().unwrap(),
+            Ok((Token::LBracket, Span { start: 0, end: 1 }))
+        );
+        assert_eq!(
+            tokenizer.next().unwrap(),
+            Ok((Token::Number(1.0), Span { start: 1, end: 2 }))
+        );
+        assert_eq!(
+            tokenizer.next(),
+            Some(Err(ParseError::UnexpectedChar { ch: ';', pos: 3 }))
+        );
+        assert_eq!(tokenizer.next(), None);
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+
+use crate::pointer::escape_token;
+use crate::{JsonValue, Map};
+
+/// A value with no TOML form, such as `null`, found at `path` (a JSON
+/// Pointer, empty for the root).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TomlError {
+    pub path: String,
+    pub found: &'static str,
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "root"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: TOML has no {} values", path, self.found)
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+impl JsonValue {
+    /// Converts to a TOML value. Whole numbers that fit become integers,
+    /// other numbers floats. `null` and `RawJson` have no TOML form.
+    pub fn to_toml(&self) -> Result<toml::Value, TomlError> {
+        to_toml_at(self, &mut String::new())
+    }
+}
+
+fn to_toml_at(value: &JsonValue, path: &mut String) -> Result<toml::Value, TomlError> {
+    Ok(match value {
+        JsonValue::Bool(b) => toml::Value::Boolean(*b),
+        JsonValue::Int(i) => toml::Value::Integer(*i),
+        JsonValue::Number(n)
+            if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 =>
+        {
+            toml::Value::Integer(*n as i64)
+        }
+        JsonValue::Number(n) => toml::Value::Float(*n),
+        JsonValue::RawNumber(raw) => toml::Value::Float(raw.parse().unwrap_or(f64::NAN)),
+        JsonValue::String(s) => toml::Value::String(s.clone()),
+        JsonValue::Array(arr) => {
+            let mut items = Vec::with_capacity(arr.len());
+            for (i, item) in arr.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/{}", i));
+                items.push(to_toml_at(item, path)?);
+                path.truncate(len);
+            }
+            toml::Value::Array(items)
+        }
+        JsonValue::Obj(map) => {
+            let mut table = toml::Table::new();
+            for (key, item) in map {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&escape_token(key));
+                table.insert(key.clone(), to_toml_at(item, path)?);
+                path.truncate(len);
+            }
+            toml::Value::Table(table)
+        }
+        JsonValue::Null | JsonValue::RawJson(_) => {
+            return Err(TomlError {
+                path: path.clone(),
+                found: value.type_name(),
+            });
+        }
+    })
+}
+
+/// Integers an `f64` holds exactly become numbers, larger ones `Int`.
+/// Datetimes become strings in their TOML form, and table keys come in the
+/// sorted order `toml` keeps them in.
+impl From<toml::Value> for JsonValue {
+    fn from(value: toml::Value) -> Self {
+        match value {
+            toml::Value::String(s) => JsonValue::String(s),
+            toml::Value::Integer(i) if i.unsigned_abs() <= 1 << 53 => JsonValue::Number(i as f64),
+            toml::Value::Integer(i) => JsonValue::Int(i),
+            toml::Value::Float(n) => JsonValue::Number(n),
+            toml::Value::Boolean(b) => JsonValue::Bool(b),
+            toml::Value::Datetime(dt) => JsonValue::String(dt.to_string()),
+            toml::Value::Array(arr) => JsonValue::Array(arr.into_iter().map(Into::into).collect()),
+            toml::Value::Table(table) => JsonValue::Obj(
+                table
+                    .into_iter()
+                    .map(|(k, v)| (k, v.into()))
+                    .collect::<Map>(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_round_trip() {
+        let value = from_str(
+            r#"{"name": "server", "port": 8080, "ratio": 0.75, "debug": false,
+                "hosts": ["a", "b"], "limits": {"cpu": 2, "big": 9007199254740993e0}}"#,
+        )
+        .unwrap();
+        let toml = value.to_toml().unwrap();
+        assert_eq!(toml["port"], toml::Value::Integer(8080));
+        assert_eq!(toml["ratio"], toml::Value::Float(0.75));
+        assert_eq!(toml["limits"]["cpu"], toml::Value::Integer(2));
+        assert_eq!(JsonValue::from(toml), value);
+
+        let text = "title = \"x\"\nwhen = 1979-05-27T07:32:00Z\n[owner]\nids = [1, 2]\n";
+        let value = JsonValue::from(text.parse::<toml::Value>().unwrap());
+        assert_eq!(
+            value,
+            from_str(r#"{"title": "x", "when": "1979-05-27T07:32:00Z", "owner": {"ids": [1, 2]}}"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_null_has_no_toml_form() {
+        let value = from_str(r#"{"a": [1, {"b/c": null}]}"#).unwrap();
+        let error = value.to_toml().unwrap_err();
+        assert_eq!(
+            error,
+            TomlError {
+                path: "/a/1/b~1c".to_string(),
+                found: "null"
+            }
+        );
+        assert_eq!(error.to_string(), "/a/1/b~1c: TOML has no null values");
+        assert!(JsonValue::Null.to_toml().is_err());
+    }
+}
@@ -0,0 +1,464 @@
+use std::io::{self, Read};
+
+use crate::escape::decode_escape;
+use crate::{Error, LineColumn, ParseError};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Checks that a reader holds one JSON document without building it.
+///
+/// Accepts the same grammar as `from_str` with default options. Memory use
+/// only grows with the nesting depth, so it suits inputs far larger than
+/// what fits in memory.
+#[derive(Debug)]
+pub struct Validator {
+    state: State,
+    /// Open containers, true for objects.
+    stack: Vec<bool>,
+    pos: usize,
+    line: usize,
+    column: usize,
+    error_at: Option<LineColumn>,
+}
+
+#[derive(Debug)]
+enum State {
+    /// A value must follow: at the start, or after a colon.
+    Value,
+    /// After `[` or a comma in an array.
+    ValueOrClose,
+    /// After `{` or a comma in an object.
+    KeyOrClose,
+    Colon,
+    AfterValue,
+    String {
+        key: bool,
+    },
+    Escape {
+        key: bool,
+        start: usize,
+        /// What follows the backslash, at most `uXXXX\uXXXX`.
+        seq: String,
+    },
+    Number(Number),
+    Literal {
+        word: &'static str,
+        start: usize,
+        matched: usize,
+    },
+    Done,
+}
+
+/// Where a number is in `-? digits (. digits)? ([eE] [+-]? digits)?`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Number {
+    Minus,
+    Int,
+    Dot,
+    Frac,
+    Exp,
+    ExpSign,
+    ExpDigits,
+}
+
+impl Number {
+    fn next(self, ch: char) -> Option<Number> {
+        match (self, ch) {
+            (Number::Minus | Number::Int, '0'..='9') => Some(Number::Int),
+            (Number::Int, '.') => Some(Number::Dot),
+            (Number::Dot | Number::Frac, '0'..='9') => Some(Number::Frac),
+            (Number::Int | Number::Frac, 'e' | 'E') => Some(Number::Exp),
+            (Number::Exp, '+' | '-') => Some(Number::ExpSign),
+            (Number::Exp | Number::ExpSign | Number::ExpDigits, '0'..='9') => {
+                Some(Number::ExpDigits)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_complete(self) -> bool {
+        matches!(self, Number::Int | Number::Frac | Number::ExpDigits)
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Validator::new()
+    }
+}
+
+impl Validator {
+    pub fn new() -> Validator {
+        Validator {
+            state: State::Value,
+            stack: vec![],
+            pos: 0,
+            line: 1,
+            column: 1,
+            error_at: None,
+        }
+    }
+
+    /// Reads `reader` to the end in fixed size chunks and checks it.
+    pub fn check<R: Read>(&mut self, mut reader: R) -> Result<(), Error> {
+        let mut buf = vec![0; CHUNK_SIZE];
+        // Bytes of a character split across two reads
+        let mut carried = 0;
+        loop {
+            let read = match reader.read(&mut buf[carried..]) {
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let end = carried + read;
+            let (valid, invalid) = match std::str::from_utf8(&buf[..end]) {
+                Ok(text) => (text, false),
+                Err(e) => {
+                    let text = std::str::from_utf8(&buf[..e.valid_up_to()]).unwrap();
+                    // Only an incomplete character at the end is worth another read
+                    (text, e.error_len().is_some() || read == 0)
+                }
+            };
+            let valid_len = valid.len();
+            self.feed(valid)?;
+            if invalid {
+                return Err(self
+                    .fail(ParseError::InvalidUtf8 { offset: self.pos })
+                    .into());
+            }
+            if read == 0 {
+                return Ok(self.finish()?);
+            }
+            buf.copy_within(valid_len..end, 0);
+            carried = end - valid_len;
+        }
+    }
+
+    /// Checks the next piece of the document.
+    pub fn feed(&mut self, chunk: &str) -> Result<(), ParseError> {
+        for ch in chunk.chars() {
+            if let Err(e) = self.step(ch) {
+                return Err(self.fail(e));
+            }
+            self.pos += ch.len_utf8();
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the document is complete once all of it was fed.
+    pub fn finish(&mut self) -> Result<(), ParseError> {
+        let result = match self.state {
+            _ if !self.stack.is_empty() => Err(ParseError::UnexpectedEof),
+            State::Done => Ok(()),
+            State::Number(number) if number.is_complete() => Ok(()),
+            State::Literal { word, matched, .. } if matched == word.len() => Ok(()),
+            State::Literal { word, start, .. } => Err(unexpected_word(word, start)),
+            _ => Err(ParseError::UnexpectedEof),
+        };
+        result.map_err(|e| self.fail(e))
+    }
+
+    /// Line and column where checking stopped: the error's position after a
+    /// failure, the end of what was fed otherwise.
+    pub fn line_column(&self) -> LineColumn {
+        self.error_at.unwrap_or(LineColumn {
+            line: self.line,
+            column: self.column,
+        })
+    }
+
+    /// Remembers where `error` happened. Errors only ever point back within
+    /// the current line and over ASCII, so the column can be worked out from
+    /// the current one.
+    fn fail(&mut self, error: ParseError) -> ParseError {
+        let back = error.position().map_or(0, |pos| self.pos - pos);
+        self.error_at = Some(LineColumn {
+            line: self.line,
+            column: self.column - back,
+        });
+        error
+    }
+
+    fn step(&mut self, ch: char) -> Result<(), ParseError> {
+        let pos = self.pos;
+        let is_whitespace = matches!(ch, ' ' | '\t' | '\n' | '\r');
+        match &mut self.state {
+            State::Done if is_whitespace => {}
+            State::Done => return Err(ParseError::TrailingCharacters { pos }),
+            State::Value
+            | State::ValueOrClose
+            | State::KeyOrClose
+            | State::Colon
+            | State::AfterValue
+                if is_whitespace => {}
+            State::Value => self.start_value(ch, pos)?,
+            State::ValueOrClose if ch == ']' => self.close(),
+            State::ValueOrClose => self.start_value(ch, pos)?,
+            State::KeyOrClose => match ch {
+                '}' => self.close(),
+                '"' => self.state = State::String { key: true },
+                _ => return Err(ParseError::ExpectedKey { pos }),
+            },
+            State::Colon if ch == ':' => self.state = State::Value,
+            State::Colon => return Err(expected(':', ch, pos)),
+            State::AfterValue => {
+                let object = self.stack.last() == Some(&true);
+                match ch {
+                    ',' if object => self.state = State::KeyOrClose,
+                    ',' => self.state = State::ValueOrClose,
+                    '}' if object => self.close(),
+                    ']' if !object => self.close(),
+                    _ => return Err(expected(if object { '}' } else { ']' }, ch, pos)),
+                }
+            }
+            State::String { key } => match ch {
+                '"' if *key => self.state = State::Colon,
+                '"' => self.end_value(),
+                '\\' => {
+                    self.state = State::Escape {
+                        key: *key,
+                        start: pos,
+                        seq: String::new(),
+                    }
+                }
+                '\0' => return Err(ParseError::UnexpectedChar { ch, pos }),
+                _ => {}
+            },
+            State::Escape { key, start, seq } => {
+                seq.push(ch);
+                if !escape_prefix_ok(seq) {
+                    return Err(ParseError::InvalidEscape { pos: *start });
+                }
+                let needed = if !seq.starts_with('u') {
+                    1
+                } else if seq.len() >= 5 && (0xD800..0xDC00).contains(&hex(&seq[1..5])) {
+                    11
+                } else {
+                    5
+                };
+                if seq.len() == needed {
+                    if decode_escape(seq).is_none() {
+                        return Err(ParseError::InvalidEscape { pos: *start });
+                    }
+                    self.state = State::String { key: *key };
+                }
+            }
+            State::Number(number) => match number.next(ch) {
+                Some(next) => *number = next,
+                None if number.is_complete() => {
+                    self.end_value();
+                    return self.step(ch);
+                }
+                None => return Err(ParseError::UnexpectedChar { ch, pos }),
+            },
+            State::Literal {
+                word,
+                start,
+                matched,
+            } => {
+                if *matched < word.len() {
+                    if word[*matched..].starts_with(ch) {
+                        *matched += 1;
+                    } else {
+                        return Err(unexpected_word(word, *start));
+                    }
+                } else if ch.is_alphanumeric() || ch == '_' {
+                    return Err(ParseError::UnexpectedChar { ch, pos });
+                } else {
+                    self.end_value();
+                    return self.step(ch);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn start_value(&mut self, ch: char, pos: usize) -> Result<(), ParseError> {
+        self.state = match ch {
+            '{' => {
+                self.stack.push(true);
+                State::KeyOrClose
+            }
+            '[' => {
+                self.stack.push(false);
+                State::ValueOrClose
+            }
+            '"' => State::String { key: false },
+            '-' => State::Number(Number::Minus),
+            '0'..='9' => State::Number(Number::Int),
+            't' | 'f' | 'n' => State::Literal {
+                word: match ch {
+                    't' => "true",
+                    'f' => "false",
+                    _ => "null",
+                },
+                start: pos,
+                matched: 1,
+            },
+            _ => return Err(ParseError::UnexpectedChar { ch, pos }),
+        };
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.stack.pop();
+        self.end_value();
+    }
+
+    fn end_value(&mut self) {
+        self.state = if self.stack.is_empty() {
+            State::Done
+        } else {
+            State::AfterValue
+        };
+    }
+}
+
+/// Whether `seq` can still start a valid escape, so a bad one is reported
+/// before it runs past the end of the line.
+fn escape_prefix_ok(seq: &str) -> bool {
+    seq.char_indices().all(|(i, ch)| match i {
+        0 => "\"\\/bfnrtu".contains(ch),
+        5 => ch == '\\',
+        6 => ch == 'u',
+        _ => ch.is_ascii_hexdigit(),
+    })
+}
+
+fn hex(digits: &str) -> u32 {
+    u32::from_str_radix(digits, 16).unwrap_or(0)
+}
+
+/// A character that can't start any token is unexpected wherever it shows
+/// up, as the tokenizer reports it before the parser sees what it expected.
+fn expected(expected: char, found: char, pos: usize) -> ParseError {
+    if !matches!(
+        found,
+        '{' | '}' | '[' | ']' | ':' | ',' | '"' | '-' | '0'..='9' | 't' | 'f' | 'n'
+    ) {
+        return ParseError::UnexpectedChar { ch: found, pos };
+    }
+    ParseError::Expected {
+        expected,
+        found,
+        pos,
+    }
+}
+
+/// The tokenizer reports a misspelled literal at its first character.
+fn unexpected_word(word: &str, start: usize) -> ParseError {
+    ParseError::UnexpectedChar {
+        ch: word.chars().next().unwrap(),
+        pos: start,
+    }
+}
+
+/// Checks a whole reader with a fresh `Validator`.
+pub fn validate_reader<R: Read>(reader: R) -> Result<(), Error> {
+    Validator::new().check(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    fn validate(input: &str) -> Result<(), ParseError> {
+        let mut validator = Validator::new();
+        validator.feed(input)?;
+        validator.finish()
+    }
+
+    #[test]
+    fn test_validate_matches_parser() {
+        let inputs = [
+            r#"{"a": [1, -2.5e+3, true, false, null, "x\"é😀"], "b": {}}"#,
+            " [ ] ",
+            "[1,]",
+            r#"{"a":1,}"#,
+            "007",
+            "",
+            "[1 2]",
+            r#"{"a" 1}"#,
+            "{1: 2}",
+            r#"{"a": 1]"#,
+            "[1,,2]",
+            "[1] x",
+            "nullx",
+            "tru",
+            "[fals]",
+            "-",
+            "1.",
+            "1e+",
+            r#""\x""#,
+            r#""\ud800x""#,
+            r#""\udc00""#,
+            r#""open"#,
+            r#""\u0000""#,
+            "\"a\0\"",
+            "[[",
+            "[1; 2]",
+            r#"{"a" ; 1}"#,
+            "[1,;2]",
+            "1;",
+            "[1 x]",
+            r#"{"a" x 1}"#,
+            "[1 true]",
+        ];
+        for input in inputs {
+            let expected = from_str(input).map(|_| ());
+            assert_eq!(validate(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_validate_reader_chunks() {
+        let item = r#"{"name": "café é😀", "n": [1.5, -3, true, null]}"#;
+        let input = format!("[{}]", vec![item; 20_000].join(",\n"));
+        assert!(input.len() > 2 * CHUNK_SIZE);
+        assert_eq!(validate_reader(input.as_bytes()), Ok(()));
+
+        // Cut off somewhere in the middle of a later chunk
+        let broken = &input[..input.len() - 1000];
+        let mut validator = Validator::new();
+        assert_eq!(
+            validator.check(broken.as_bytes()),
+            Err(Error::Parse(ParseError::UnexpectedEof))
+        );
+
+        let mut validator = Validator::new();
+        let input = "[1,\n  2 3]";
+        assert_eq!(
+            validator.check(input.as_bytes()),
+            Err(Error::Parse(ParseError::Expected {
+                expected: ']',
+                found: '3',
+                pos: 8
+            }))
+        );
+        assert_eq!(validator.line_column(), LineColumn::from_offset(input, 8));
+
+        let mut validator = Validator::new();
+        let input = "[\"tr\\q\"]";
+        assert!(validator.check(input.as_bytes()).is_err());
+        assert_eq!(validator.line_column(), LineColumn { line: 1, column: 5 });
+    }
+
+    #[test]
+    fn test_validate_invalid_utf8() {
+        assert_eq!(
+            validate_reader(&b"[\"a\xff\"]"[..]),
+            Err(Error::Parse(ParseError::InvalidUtf8 { offset: 3 }))
+        );
+        assert_eq!(
+            validate_reader(&b"[\"\xc3"[..]),
+            Err(Error::Parse(ParseError::InvalidUtf8 { offset: 2 }))
+        );
+    }
+}
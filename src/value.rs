@@ -0,0 +1,1010 @@
+use std::fmt;
+
+use indexmap::IndexMap;
+
+use crate::escape::write_quoted;
+use crate::{SerializeOptions, TypeError};
+
+/// Object storage, keeps keys in insertion order.
+pub type Map = IndexMap<String, JsonValue>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum JsonValue {
+    #[default]
+    Null,
+    Number(f64),
+    /// A number known to be an integer, kept exactly. Only equal to another
+    /// `Int`, not to the `Number` of the same value.
+    Int(i64),
+    /// Text of an integer too large for `Int`, kept exactly as written. See
+    /// `BigIntegers::Exact`.
+    RawNumber(String),
+    String(String),
+    Bool(bool),
+    Array(Vec<JsonValue>),
+    Obj(Map),
+    /// Text of a value kept as written, see `ParserOptions::raw_keys`. It is
+    /// written back out unchanged and unchecked, so serializing can give
+    /// invalid JSON if the text isn't.
+    RawJson(String),
+}
+
+/// What `JsonValue::get_field` found under a key, telling an explicit
+/// `null` apart from a key that isn't there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldState<'a> {
+    Missing,
+    Null,
+    Present(&'a JsonValue),
+}
+
+impl JsonValue {
+    /// Builds an object with the entries in order. A repeated key keeps its
+    /// first position and its last value, as when parsing.
+    pub fn from_pairs(iter: impl IntoIterator<Item = (String, JsonValue)>) -> JsonValue {
+        JsonValue::Obj(iter.into_iter().collect())
+    }
+
+    /// Returns the value stored under `key` if this is an object.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Obj(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Like `get`, but tells `{"a": null}` apart from `{}`. Anything but an
+    /// object has every field missing.
+    pub fn get_field(&self, key: &str) -> FieldState<'_> {
+        match self.get(key) {
+            None => FieldState::Missing,
+            Some(JsonValue::Null) => FieldState::Null,
+            Some(value) => FieldState::Present(value),
+        }
+    }
+
+    /// For an object tagged with its kind, as in `{"type": "dog", ...}`:
+    /// the string under `tag_key` and the whole object, to dispatch on when
+    /// building an enum. `None` unless this is an object with a string tag.
+    pub fn tagged(&self, tag_key: &str) -> Option<(&str, &JsonValue)> {
+        match self.get(tag_key)? {
+            JsonValue::String(tag) => Some((tag, self)),
+            _ => None,
+        }
+    }
+
+    /// First element of an array, `None` if it is empty or not an array.
+    pub fn first(&self) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Array(arr) => arr.first(),
+            _ => None,
+        }
+    }
+
+    /// Last element of an array, `None` if it is empty or not an array.
+    pub fn last(&self) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Array(arr) => arr.last(),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this is an object with an entry for `key`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Keys of an object in insertion order, `None` for other values.
+    pub fn keys(&self) -> Option<Vec<&String>> {
+        match self {
+            JsonValue::Obj(map) => Some(map.keys().collect()),
+            _ => None,
+        }
+    }
+
+    /// Values of an object in insertion order, `None` for other values.
+    pub fn values(&self) -> Option<Vec<&JsonValue>> {
+        match self {
+            JsonValue::Obj(map) => Some(map.values().collect()),
+            _ => None,
+        }
+    }
+
+    /// Like `get`, but falls back to `default` when the key is missing or this
+    /// isn't an object.
+    pub fn get_or<'a>(&'a self, key: &str, default: &'a JsonValue) -> &'a JsonValue {
+        self.get(key).unwrap_or(default)
+    }
+
+    /// JavaScript-like truthiness: `null`, `false`, `0`, `""`, `[]` and `{}`
+    /// are falsy, everything else is truthy.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            JsonValue::Null => false,
+            JsonValue::Bool(b) => *b,
+            JsonValue::Number(n) => *n != 0.0 && !n.is_nan(),
+            JsonValue::Int(i) => *i != 0,
+            JsonValue::RawNumber(_) => true,
+            JsonValue::String(s) => !s.is_empty(),
+            JsonValue::Array(arr) => !arr.is_empty(),
+            JsonValue::Obj(map) => !map.is_empty(),
+            JsonValue::RawJson(_) => true,
+        }
+    }
+
+    /// Text meant for logs: strings without their quotes, scalars as their
+    /// JSON text, containers as compact JSON.
+    pub fn to_display_string(&self) -> String {
+        match self {
+            JsonValue::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Encodes a flat object as URL query parameters, `a=1&b=x%20y`, with
+    /// values written as by `to_display_string` and `null` as an empty
+    /// value. `None` for anything but an object of scalars.
+    pub fn to_query_string(&self) -> Option<String> {
+        let JsonValue::Obj(map) = self else {
+            return None;
+        };
+        let mut pairs = Vec::with_capacity(map.len());
+        for (key, value) in map {
+            let value = match value {
+                JsonValue::Null => String::new(),
+                JsonValue::Bool(_)
+                | JsonValue::Number(_)
+                | JsonValue::Int(_)
+                | JsonValue::RawNumber(_)
+                | JsonValue::String(_) => value.to_display_string(),
+                _ => return None,
+            };
+            pairs.push(format!(
+                "{}={}",
+                percent_encode(key),
+                percent_encode(&value)
+            ));
+        }
+        Some(pairs.join("&"))
+    }
+
+    /// Serializes with every element on its own line, indented by two spaces.
+    /// Empty containers stay as `[]` and `{}`.
+    pub fn to_pretty_string(&self) -> String {
+        self.to_string_with_options(&SerializeOptions {
+            indent: Some(2),
+            ..Default::default()
+        })
+    }
+
+    /// Like `to_pretty_string`, but keeps each array and object that fits
+    /// within `width` columns on one line.
+    pub fn to_pretty_string_width(&self, width: usize) -> String {
+        self.to_string_with_options(&SerializeOptions {
+            indent: Some(2),
+            max_width: Some(width),
+            ..Default::default()
+        })
+    }
+
+    /// UTF-8 bytes of the compact serialization.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+
+    /// How many arrays/objects deep the tree goes: 0 for a scalar, 1 for a
+    /// flat container. Same counting as `ParserOptions::max_depth`.
+    pub fn depth(&self) -> usize {
+        match self {
+            JsonValue::Array(arr) => 1 + arr.iter().map(JsonValue::depth).max().unwrap_or(0),
+            JsonValue::Obj(map) => 1 + map.values().map(JsonValue::depth).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Number of values in the tree, this one included. Object keys are not
+    /// counted, only their values.
+    pub fn node_count(&self) -> usize {
+        match self {
+            JsonValue::Array(arr) => 1 + arr.iter().map(JsonValue::node_count).sum::<usize>(),
+            JsonValue::Obj(map) => 1 + map.values().map(JsonValue::node_count).sum::<usize>(),
+            _ => 1,
+        }
+    }
+
+    /// Combines every value in the tree into one result, visiting each node
+    /// before its contents, in document order. Object keys are not visited.
+    pub fn fold<T>(&self, init: T, mut f: impl FnMut(T, &JsonValue) -> T) -> T {
+        self.fold_with(init, &mut f)
+    }
+
+    fn fold_with<T>(&self, acc: T, f: &mut impl FnMut(T, &JsonValue) -> T) -> T {
+        let acc = f(acc, self);
+        match self {
+            JsonValue::Array(arr) => arr.iter().fold(acc, |acc, v| v.fold_with(acc, f)),
+            JsonValue::Obj(map) => map.values().fold(acc, |acc, v| v.fold_with(acc, f)),
+            _ => acc,
+        }
+    }
+
+    /// Rough number of heap bytes owned by this value: string and container
+    /// capacities, recursively. The value itself isn't counted, and the
+    /// object index is estimated from the map's capacity.
+    pub fn memory_size(&self) -> usize {
+        match self {
+            JsonValue::Null | JsonValue::Bool(_) | JsonValue::Number(_) | JsonValue::Int(_) => 0,
+            JsonValue::String(s) | JsonValue::RawNumber(s) | JsonValue::RawJson(s) => s.capacity(),
+            JsonValue::Array(arr) => {
+                arr.capacity() * size_of::<JsonValue>()
+                    + arr.iter().map(JsonValue::memory_size).sum::<usize>()
+            }
+            JsonValue::Obj(map) => {
+                // Entries are stored with their hash, plus one index slot each
+                let entry = size_of::<(String, JsonValue)>() + 2 * size_of::<usize>();
+                map.capacity() * entry
+                    + map
+                        .iter()
+                        .map(|(k, v)| k.capacity() + v.memory_size())
+                        .sum::<usize>()
+            }
+        }
+    }
+
+    /// Recursively reorders the entries of every object by key.
+    pub fn sort_keys(&mut self) {
+        match self {
+            JsonValue::Obj(map) => {
+                map.sort_keys();
+                map.values_mut().for_each(JsonValue::sort_keys);
+            }
+            JsonValue::Array(arr) => arr.iter_mut().for_each(JsonValue::sort_keys),
+            _ => {}
+        }
+    }
+
+    /// Replaces every node equal to `target`, at any depth, with a copy of
+    /// `replacement`. Replaced nodes are not searched any further.
+    pub fn replace_all(&mut self, target: &JsonValue, replacement: &JsonValue) {
+        if self == target {
+            *self = replacement.clone();
+            return;
+        }
+        match self {
+            JsonValue::Obj(map) => map
+                .values_mut()
+                .for_each(|v| v.replace_all(target, replacement)),
+            JsonValue::Array(arr) => arr
+                .iter_mut()
+                .for_each(|v| v.replace_all(target, replacement)),
+            _ => {}
+        }
+    }
+
+    /// Appends `value` to an array. Any other variant is left unchanged and
+    /// reported as an error.
+    pub fn push(&mut self, value: JsonValue) -> Result<(), TypeError> {
+        match self {
+            JsonValue::Array(arr) => {
+                arr.push(value);
+                Ok(())
+            }
+            other => Err(TypeError::new("array", other)),
+        }
+    }
+
+    /// Appends every value of `values` to an array, see `push`.
+    pub fn extend<I: IntoIterator<Item = JsonValue>>(
+        &mut self,
+        values: I,
+    ) -> Result<(), TypeError> {
+        match self {
+            JsonValue::Array(arr) => {
+                arr.extend(values);
+                Ok(())
+            }
+            other => Err(TypeError::new("array", other)),
+        }
+    }
+
+    /// Merges `other` into this value. Where both sides are objects their
+    /// keys are merged recursively, anywhere else `other` wins, arrays and
+    /// `null` included.
+    pub fn deep_merge(&mut self, other: JsonValue) {
+        match (self, other) {
+            (JsonValue::Obj(map), JsonValue::Obj(other)) => {
+                for (key, value) in other {
+                    match map.get_mut(&key) {
+                        Some(existing) => existing.deep_merge(value),
+                        None => {
+                            map.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (this, other) => *this = other,
+        }
+    }
+
+    /// Keeps only the elements of an array, or the entries of an object, for
+    /// which `f` returns true. `f` gets the key for object entries and `None`
+    /// for array elements. Only this level is filtered, and anything but a
+    /// container is left alone.
+    pub fn retain(&mut self, mut f: impl FnMut(Option<&str>, &JsonValue) -> bool) {
+        match self {
+            JsonValue::Array(arr) => arr.retain(|v| f(None, v)),
+            JsonValue::Obj(map) => map.retain(|k, v| f(Some(k), v)),
+            _ => {}
+        }
+    }
+
+    /// Copy of the tree keeping only the object entries, at any depth, for
+    /// which `keep` returns true given their key and value. A dropped entry
+    /// takes its whole subtree with it. Array elements are always kept,
+    /// with their contents filtered.
+    pub fn filter_tree(&self, keep: impl Fn(&str, &JsonValue) -> bool) -> JsonValue {
+        self.filter_tree_with(&keep)
+    }
+
+    fn filter_tree_with(&self, keep: &impl Fn(&str, &JsonValue) -> bool) -> JsonValue {
+        match self {
+            JsonValue::Obj(map) => JsonValue::Obj(
+                map.iter()
+                    .filter(|(k, v)| keep(k, v))
+                    .map(|(k, v)| (k.clone(), v.filter_tree_with(keep)))
+                    .collect(),
+            ),
+            JsonValue::Array(arr) => {
+                JsonValue::Array(arr.iter().map(|v| v.filter_tree_with(keep)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Recursively removes object entries whose value is `null`. Array
+    /// elements are kept, since removing them would shift the others.
+    pub fn prune_nulls(&mut self) {
+        self.prune(false);
+    }
+
+    /// Like `prune_nulls`, and also removes entries left holding an empty
+    /// array or object, including ones that only became empty by pruning.
+    pub fn prune_empty(&mut self) {
+        self.prune(true);
+    }
+
+    fn prune(&mut self, empty: bool) {
+        match self {
+            JsonValue::Obj(map) => {
+                map.values_mut().for_each(|v| v.prune(empty));
+                map.retain(|_, v| match v {
+                    JsonValue::Null => false,
+                    JsonValue::Array(arr) => !(empty && arr.is_empty()),
+                    JsonValue::Obj(map) => !(empty && map.is_empty()),
+                    _ => true,
+                });
+            }
+            JsonValue::Array(arr) => arr.iter_mut().for_each(|v| v.prune(empty)),
+            _ => {}
+        }
+    }
+
+    /// Turns every `Number` holding a whole value that fits an `i64` into an
+    /// `Int`, for consumers that only take integers. Other numbers are kept.
+    pub fn coerce_integers(&mut self) {
+        match self {
+            JsonValue::Number(n)
+                if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 =>
+            {
+                *self = JsonValue::Int(*n as i64);
+            }
+            JsonValue::Array(arr) => arr.iter_mut().for_each(JsonValue::coerce_integers),
+            JsonValue::Obj(map) => map.values_mut().for_each(JsonValue::coerce_integers),
+            _ => {}
+        }
+    }
+
+    /// Renames every object key, at any depth, to `f` of it. If two keys of
+    /// one object get the same name, the later value wins, in the place of
+    /// the first.
+    pub fn map_keys(&mut self, f: impl Fn(&str) -> String) {
+        self.map_keys_with(&f);
+    }
+
+    fn map_keys_with(&mut self, f: &impl Fn(&str) -> String) {
+        match self {
+            JsonValue::Obj(map) => {
+                *map = std::mem::take(map)
+                    .into_iter()
+                    .map(|(k, mut v)| {
+                        v.map_keys_with(f);
+                        (f(&k), v)
+                    })
+                    .collect();
+            }
+            JsonValue::Array(arr) => arr.iter_mut().for_each(|v| v.map_keys_with(f)),
+            _ => {}
+        }
+    }
+
+    /// Rewrites the tree into a canonical form so that equivalent documents
+    /// end up identical, down to their serialized text: keys are sorted and
+    /// `-0` becomes `0`. `1.0` and `1` are already the same `f64`, and an
+    /// `Int` or `RawNumber` that an `f64` holds exactly becomes a `Number`.
+    pub fn normalize(&mut self) {
+        match self {
+            JsonValue::Number(n) if *n == 0.0 => *n = 0.0,
+            JsonValue::Int(i) => {
+                let n = *i as f64;
+                // `i64::MAX as f64` rounds up to 2^63, out of range
+                if n < i64::MAX as f64 && n as i64 == *i {
+                    *self = JsonValue::Number(n);
+                }
+            }
+            JsonValue::RawNumber(raw) => {
+                if let Ok(n) = raw.parse::<f64>()
+                    && n.is_finite()
+                    && format!("{:.0}", n) == *raw
+                {
+                    *self = JsonValue::Number(n);
+                }
+            }
+            JsonValue::Obj(map) => {
+                map.sort_keys();
+                map.values_mut().for_each(JsonValue::normalize);
+            }
+            JsonValue::Array(arr) => arr.iter_mut().for_each(JsonValue::normalize),
+            _ => {}
+        }
+    }
+
+    /// The JSON name of this value's type, for messages and logs.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::Null => "null",
+            JsonValue::Bool(_) => "boolean",
+            JsonValue::Number(_) | JsonValue::Int(_) | JsonValue::RawNumber(_) => "number",
+            JsonValue::String(_) => "string",
+            JsonValue::Array(_) => "array",
+            JsonValue::Obj(_) => "object",
+            JsonValue::RawJson(_) => "raw",
+        }
+    }
+}
+
+/// Escapes every byte outside the RFC 3986 unreserved set as `%XX`.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Non-finite numbers have no JSON form, they are written as the literals
+/// the lenient parser reads back.
+pub(crate) fn write_number<W: fmt::Write>(out: &mut W, n: f64) -> fmt::Result {
+    if n.is_nan() {
+        out.write_str("NaN")
+    } else if n.is_infinite() {
+        out.write_str(if n > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        write!(out, "{}", n)
+    }
+}
+
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonValue::Null => write!(f, "null"),
+            JsonValue::Bool(b) => write!(f, "{}", b),
+            JsonValue::Number(n) => write_number(f, *n),
+            JsonValue::Int(i) => write!(f, "{}", i),
+            JsonValue::RawNumber(raw) => f.write_str(raw),
+            JsonValue::String(s) => write_quoted(f, s),
+            JsonValue::RawJson(raw) => f.write_str(raw),
+            JsonValue::Array(arr) => {
+                write!(f, "[")?;
+                for (i, item) in arr.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            JsonValue::Obj(map) => {
+                write!(f, "{{")?;
+                let mut first = true;
+                for (key, value) in map.iter() {
+                    if !first {
+                        write!(f, ",")?;
+                    }
+                    write_quoted(f, key)?;
+                    write!(f, ":{}", value)?;
+                    first = false;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn test_default() {
+        assert_eq!(JsonValue::default(), JsonValue::Null);
+
+        let mut value = from_str("[1]").unwrap();
+        let taken = std::mem::take(&mut value);
+        assert_eq!(value, JsonValue::Null);
+        assert_eq!(taken, JsonValue::Array(vec![JsonValue::Number(1.0)]));
+    }
+
+    #[test]
+    fn test_from_pairs() {
+        let pairs = vec![
+            ("b".to_string(), JsonValue::Number(1.0)),
+            ("a".to_string(), JsonValue::Array(vec![JsonValue::Null])),
+            ("b".to_string(), JsonValue::Bool(true)),
+        ];
+        let value = JsonValue::from_pairs(pairs);
+        assert_eq!(
+            value,
+            from_str(r#"{"b": 1, "a": [null], "b": true}"#).unwrap()
+        );
+        assert_eq!(value.to_string(), r#"{"b":true,"a":[null]}"#);
+        assert_eq!(JsonValue::from_pairs([]), JsonValue::Obj(Map::new()));
+    }
+
+    #[test]
+    fn test_first_last() {
+        let value = from_str(r#"[1, "two", null]"#).unwrap();
+        assert_eq!(value.first(), Some(&JsonValue::Number(1.0)));
+        assert_eq!(value.last(), Some(&JsonValue::Null));
+
+        let empty = JsonValue::Array(vec![]);
+        assert_eq!(empty.first(), None);
+        assert_eq!(empty.last(), None);
+
+        let object = from_str(r#"{"a": 1}"#).unwrap();
+        assert_eq!(object.first(), None);
+        assert_eq!(object.last(), None);
+    }
+
+    #[test]
+    fn test_contains_key() {
+        let value = from_str(r#"{"a": null}"#).unwrap();
+        assert!(value.contains_key("a"));
+        assert!(!value.contains_key("b"));
+        assert!(!from_str(r#"["a"]"#).unwrap().contains_key("a"));
+    }
+
+    #[test]
+    fn test_keys() {
+        let value = from_str(
+            r#"{
+                "one" : 2,
+                "two" : [2, null, false],
+                "three": "third value",
+            }"#,
+        )
+        .unwrap();
+        let keys: Vec<&str> = value
+            .keys()
+            .unwrap()
+            .into_iter()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(keys, ["one", "two", "three"]);
+        assert_eq!(JsonValue::Array(vec![]).keys(), None);
+    }
+
+    #[test]
+    fn test_values() {
+        let value = from_str(r#"{"a": 1, "b": [null], "c": "x"}"#).unwrap();
+        let values = value.values().unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(
+            values,
+            [
+                &JsonValue::Number(1.0),
+                &JsonValue::Array(vec![JsonValue::Null]),
+                &JsonValue::String("x".to_string()),
+            ]
+        );
+        assert_eq!(JsonValue::Null.values(), None);
+    }
+
+    #[test]
+    fn test_get_or() {
+        let value = from_str(r#"{"limit": 30}"#).unwrap();
+        let default = JsonValue::Number(10.0);
+        assert_eq!(value.get_or("limit", &default), &JsonValue::Number(30.0));
+        assert_eq!(value.get_or("skip", &default), &default);
+        assert_eq!(JsonValue::Null.get_or("limit", &default), &default);
+    }
+
+    #[test]
+    fn test_is_truthy() {
+        for falsy in ["null", "false", "0", "-0", "\"\"", "[]", "{}"] {
+            assert!(!from_str(falsy).unwrap().is_truthy(), "{}", falsy);
+        }
+        for truthy in ["true", "1", "-0.5", "\"0\"", "[0]", r#"{"a": null}"#] {
+            assert!(from_str(truthy).unwrap().is_truthy(), "{}", truthy);
+        }
+    }
+
+    #[test]
+    fn test_to_display_string() {
+        assert_eq!(JsonValue::Null.to_display_string(), "null");
+        assert_eq!(JsonValue::Bool(true).to_display_string(), "true");
+        assert_eq!(JsonValue::Number(2.5).to_display_string(), "2.5");
+        assert_eq!(JsonValue::Number(254.0).to_display_string(), "254");
+        assert_eq!(
+            JsonValue::String("hello".to_string()).to_display_string(),
+            "hello"
+        );
+        assert_eq!(
+            from_str(r#"["a"]"#).unwrap().to_display_string(),
+            r#"["a"]"#
+        );
+    }
+
+    #[test]
+    fn test_to_query_string() {
+        let query = |input| from_str(input).unwrap().to_query_string();
+        assert_eq!(
+            query(r#"{"a":1,"b":"x y"}"#),
+            Some("a=1&b=x%20y".to_string())
+        );
+        assert_eq!(
+            query(r#"{"q&a": "é=1/2", "on": true, "none": null, "n": -2.5}"#),
+            Some("q%26a=%C3%A9%3D1%2F2&on=true&none=&n=-2.5".to_string())
+        );
+        assert_eq!(query("{}"), Some(String::new()));
+        assert_eq!(query(r#"{"a": [1]}"#), None);
+        assert_eq!(query(r#"{"a": {}}"#), None);
+        assert_eq!(query(r#"["a"]"#), None);
+        assert_eq!(query("1"), None);
+    }
+
+    #[test]
+    fn test_to_pretty_string() {
+        let value = from_str(r#"{"a": [1, {"b": null}], "c": {}, "d": []}"#).unwrap();
+        assert_eq!(
+            value.to_pretty_string(),
+            "{\n  \"a\": [\n    1,\n    {\n      \"b\": null\n    }\n  ],\n  \"c\": {},\n  \"d\": []\n}"
+        );
+        assert_eq!(JsonValue::Bool(true).to_pretty_string(), "true");
+    }
+
+    #[test]
+    fn test_depth() {
+        assert_eq!(JsonValue::Number(1.0).depth(), 0);
+        assert_eq!(from_str("[]").unwrap().depth(), 1);
+        let value = from_str(r#"{"a": {"b": {"c": 1}}, "d": [2]}"#).unwrap();
+        assert_eq!(value.depth(), 3);
+    }
+
+    #[test]
+    fn test_node_count() {
+        assert_eq!(JsonValue::Null.node_count(), 1);
+        assert_eq!(from_str("[]").unwrap().node_count(), 1);
+        let value = from_str(r#"{"a": {"b": {"c": 1}}, "d": [2, null, "x"], "e": {}}"#).unwrap();
+        assert_eq!(value.node_count(), 9);
+    }
+
+    #[test]
+    fn test_fold() {
+        let value =
+            from_str(r#"{"a": 1, "b": [2, {"c": 3.5, "d": "4"}], "e": [], "f": -0.5}"#).unwrap();
+        let sum = value.fold(0.0, |sum, v| match v {
+            JsonValue::Number(n) => sum + n,
+            _ => sum,
+        });
+        assert_eq!(sum, 6.0);
+
+        let order = value.fold(vec![], |mut names, v| {
+            names.push(v.type_name());
+            names
+        });
+        assert_eq!(
+            order,
+            [
+                "object", "number", "array", "number", "object", "number", "string", "array",
+                "number"
+            ]
+        );
+        assert_eq!(value.fold(0, |n, _| n + 1), value.node_count());
+    }
+
+    #[test]
+    fn test_type_name() {
+        let JsonValue::Array(values) = from_str(r#"[null, true, 1, "a", [], {}]"#).unwrap() else {
+            unreachable!()
+        };
+        let names: Vec<_> = values.iter().map(JsonValue::type_name).collect();
+        assert_eq!(
+            names,
+            ["null", "boolean", "number", "string", "array", "object"]
+        );
+    }
+
+    #[test]
+    fn test_memory_size() {
+        assert_eq!(JsonValue::Null.memory_size(), 0);
+        assert_eq!(JsonValue::String("hello".to_string()).memory_size(), 5);
+
+        let arr = JsonValue::Array(vec![
+            JsonValue::Number(1.0),
+            JsonValue::String("abc".to_string()),
+        ]);
+        assert_eq!(arr.memory_size(), 2 * size_of::<JsonValue>() + 3);
+
+        let small = from_str(r#"{"a": [1, 2]}"#).unwrap();
+        let large = from_str(r#"{"a": [1, 2], "b": {"c": "a longer string"}}"#).unwrap();
+        assert!(small.memory_size() > 0);
+        assert!(large.memory_size() > small.memory_size());
+    }
+
+    #[test]
+    fn test_coerce_integers() {
+        let mut value = from_str(r#"{"a": 2.0, "b": 2.5}"#).unwrap();
+        value.coerce_integers();
+        assert_eq!(value.get("a"), Some(&JsonValue::Int(2)));
+        assert_eq!(value.get("b"), Some(&JsonValue::Number(2.5)));
+
+        let mut value = from_str("[-0, 0.5, [3], {\"c\": -7}]").unwrap();
+        value.coerce_integers();
+        assert_eq!(
+            value,
+            JsonValue::Array(vec![
+                JsonValue::Int(0),
+                JsonValue::Number(0.5),
+                JsonValue::Array(vec![JsonValue::Int(3)]),
+                JsonValue::Obj(Map::from_iter([("c".to_string(), JsonValue::Int(-7))])),
+            ])
+        );
+        assert_eq!(value.to_string(), r#"[0,0.5,[3],{"c":-7}]"#);
+    }
+
+    #[test]
+    fn test_get_field() {
+        let value = from_str(r#"{"a": null, "b": 0}"#).unwrap();
+        assert_eq!(value.get_field("a"), FieldState::Null);
+        assert_eq!(
+            value.get_field("b"),
+            FieldState::Present(&JsonValue::Number(0.0))
+        );
+        assert_eq!(value.get_field("c"), FieldState::Missing);
+        assert_eq!(JsonValue::Null.get_field("a"), FieldState::Missing);
+    }
+
+    #[test]
+    fn test_tagged() {
+        let value = from_str(r#"{"type": "dog", "name": "rex"}"#).unwrap();
+        let (tag, object) = value.tagged("type").unwrap();
+        assert_eq!(tag, "dog");
+        assert_eq!(
+            object.get("name"),
+            Some(&JsonValue::String("rex".to_string()))
+        );
+
+        assert_eq!(value.tagged("kind"), None);
+        assert_eq!(from_str(r#"{"type": 1}"#).unwrap().tagged("type"), None);
+        assert_eq!(from_str(r#"["dog"]"#).unwrap().tagged("type"), None);
+    }
+
+    #[test]
+    fn test_map_keys() {
+        let snake_case = |key: &str| {
+            let mut out = String::new();
+            for ch in key.chars() {
+                if ch.is_ascii_uppercase() {
+                    out.push('_');
+                }
+                out.push(ch.to_ascii_lowercase());
+            }
+            out
+        };
+        let mut value = from_str(
+            r#"{"userName": "ann", "homeAddress": {"zipCode": "123", "lineOne": "x"},
+                "pastOrders": [{"orderId": 1}, "keepThisValue"]}"#,
+        )
+        .unwrap();
+        value.map_keys(snake_case);
+        assert_eq!(
+            value.to_string(),
+            r#"{"user_name":"ann","home_address":{"zip_code":"123","line_one":"x"},"past_orders":[{"order_id":1},"keepThisValue"]}"#
+        );
+
+        let mut value = from_str(r#"{"a": 1, "b": 2, "c": 3}"#).unwrap();
+        value.map_keys(|k| {
+            if k == "c" {
+                "a".to_string()
+            } else {
+                k.to_string()
+            }
+        });
+        assert_eq!(value.to_string(), r#"{"a":3,"b":2}"#);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut a = from_str(r#"{"a": 1.0, "b": 2, "c": [-0, {"y": 1, "x": 2}]}"#).unwrap();
+        let mut b = from_str(r#"{"c": [0.0, {"x": 2, "y": 1}], "b": 2.0, "a": 1}"#).unwrap();
+        a.normalize();
+        b.normalize();
+        assert_eq!(a, b);
+        assert_eq!(a.to_string(), b.to_string());
+        assert_eq!(a.to_string(), r#"{"a":1,"b":2,"c":[0,{"x":2,"y":1}]}"#);
+
+        let mut a = JsonValue::Array(vec![
+            JsonValue::Int(2),
+            JsonValue::Int(1 << 60),
+            JsonValue::Int(i64::MIN),
+            JsonValue::RawNumber("18446744073709551616".to_string()),
+        ]);
+        let mut b = JsonValue::Array(vec![
+            JsonValue::Number(2.0),
+            JsonValue::Number((1u64 << 60) as f64),
+            JsonValue::Number(i64::MIN as f64),
+            JsonValue::Number(18446744073709551616.0),
+        ]);
+        a.normalize();
+        b.normalize();
+        assert_eq!(a, b);
+
+        let mut inexact = JsonValue::Array(vec![
+            JsonValue::Int((1 << 53) + 1),
+            JsonValue::Int(i64::MAX),
+            JsonValue::RawNumber("18446744073709551617".to_string()),
+        ]);
+        let copy = inexact.clone();
+        inexact.normalize();
+        assert_eq!(inexact, copy);
+    }
+
+    #[test]
+    fn test_replace_all() {
+        let mut value =
+            from_str(r#"{"a": null, "b": [1, null, {"c": null}], "d": "null"}"#).unwrap();
+        value.replace_all(&JsonValue::Null, &JsonValue::Number(0.0));
+        assert_eq!(
+            value,
+            from_str(r#"{"a": 0, "b": [1, 0, {"c": 0}], "d": "null"}"#).unwrap()
+        );
+
+        let mut value = from_str("[[1], [[1]]]").unwrap();
+        value.replace_all(&from_str("[1]").unwrap(), &from_str("[[1]]").unwrap());
+        assert_eq!(value, from_str("[[[1]], [[[1]]]]").unwrap());
+    }
+
+    #[test]
+    fn test_push_extend() {
+        let mut value = from_str("[1, 2]").unwrap();
+        value.push(JsonValue::Null).unwrap();
+        value
+            .extend(["a", "b"].map(|s| JsonValue::String(s.to_string())))
+            .unwrap();
+        assert_eq!(value, from_str(r#"[1, 2, null, "a", "b"]"#).unwrap());
+
+        let mut value = from_str("{}").unwrap();
+        assert_eq!(
+            value.push(JsonValue::Null),
+            Err(TypeError {
+                expected: "array",
+                found: "object"
+            })
+        );
+        assert!(value.extend(vec![]).is_err());
+        assert_eq!(value, from_str("{}").unwrap());
+    }
+
+    #[test]
+    fn test_deep_merge() {
+        let mut value = from_str(r#"{"a": {"b": 1, "c": [1, 2]}, "d": "x", "e": 1}"#).unwrap();
+        let other = from_str(r#"{"a": {"c": [3], "f": {"g": true}}, "d": null, "h": 2}"#).unwrap();
+        value.deep_merge(other);
+        assert_eq!(
+            value,
+            from_str(r#"{"a": {"b": 1, "c": [3], "f": {"g": true}}, "d": null, "e": 1, "h": 2}"#)
+                .unwrap()
+        );
+
+        let mut value = from_str(r#"{"a": 1}"#).unwrap();
+        value.deep_merge(JsonValue::Number(2.0));
+        assert_eq!(value, JsonValue::Number(2.0));
+    }
+
+    #[test]
+    fn test_retain() {
+        let is_number = |_: Option<&str>, v: &JsonValue| v.type_name() == "number";
+        let mut value = from_str(r#"[1, "two", 3.5, null, [4], {"five": 5}, -6]"#).unwrap();
+        value.retain(is_number);
+        assert_eq!(value, from_str("[1, 3.5, -6]").unwrap());
+
+        let mut value = from_str(r#"{"a": 1, "b": "x", "ab": 2, "c": {"a": 3}}"#).unwrap();
+        value.retain(|key, _| key.is_some_and(|k| k.starts_with('a')));
+        assert_eq!(value, from_str(r#"{"a": 1, "ab": 2}"#).unwrap());
+
+        let mut value = JsonValue::Number(1.0);
+        value.retain(|_, _| false);
+        assert_eq!(value, JsonValue::Number(1.0));
+    }
+
+    #[test]
+    fn test_filter_tree() {
+        let value = from_str(
+            r#"{"id": 1, "_rev": "3-a", "items": [{"name": "x", "_tmp": true}, "_kept"],
+                "meta": {"_private": {"a": 1}, "public": {"_b": 2, "c": 3}}}"#,
+        )
+        .unwrap();
+        let filtered = value.filter_tree(|key, _| !key.starts_with('_'));
+        assert_eq!(
+            filtered,
+            from_str(
+                r#"{"id": 1, "items": [{"name": "x"}, "_kept"], "meta": {"public": {"c": 3}}}"#
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            value.get("_rev"),
+            Some(&JsonValue::String("3-a".to_string()))
+        );
+        assert_eq!(JsonValue::Null.filter_tree(|_, _| false), JsonValue::Null);
+    }
+
+    #[test]
+    fn test_prune_nulls() {
+        let input =
+            r#"{"a": null, "b": {"c": null, "d": 1}, "e": [null, {"f": null}], "g": {"h": null}}"#;
+        let mut value = from_str(input).unwrap();
+        value.prune_nulls();
+        assert_eq!(
+            value,
+            from_str(r#"{"b": {"d": 1}, "e": [null, {}], "g": {}}"#).unwrap()
+        );
+
+        let mut value = from_str(input).unwrap();
+        value.prune_empty();
+        assert_eq!(
+            value,
+            from_str(r#"{"b": {"d": 1}, "e": [null, {}]}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let value = from_str(r#"{"a": [1, 2.5, null], "b": "été"}"#).unwrap();
+        assert_eq!(value.to_bytes(), value.to_string().into_bytes());
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let mut value =
+            from_str(r#"{"b": {"z": 1, "y": [{"d": 1, "c": 2}]}, "a": null, "c": {}}"#).unwrap();
+        value.sort_keys();
+
+        let keys = |v: &JsonValue| match v {
+            JsonValue::Obj(map) => map.keys().cloned().collect::<Vec<_>>(),
+            _ => panic!("Expected object"),
+        };
+        assert_eq!(keys(&value), ["a", "b", "c"]);
+        let b = value.get("b").unwrap();
+        assert_eq!(keys(b), ["y", "z"]);
+        match b.get("y") {
+            Some(JsonValue::Array(arr)) => assert_eq!(keys(&arr[0]), ["c", "d"]),
+            _ => panic!("Expected array"),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{JsonValue, Map};
+
+/// Bottom-up transformation of a tree, driven by `JsonValue::accept`.
+///
+/// Each method receives a node and returns its replacement, or `None` to
+/// drop it from the enclosing array or object. Containers are visited after
+/// their children, so they see the already transformed contents. Every
+/// method defaults to keeping the node unchanged.
+pub trait Visitor {
+    fn visit_null(&mut self) -> Option<JsonValue> {
+        Some(JsonValue::Null)
+    }
+
+    fn visit_bool(&mut self, b: bool) -> Option<JsonValue> {
+        Some(JsonValue::Bool(b))
+    }
+
+    fn visit_number(&mut self, n: f64) -> Option<JsonValue> {
+        Some(JsonValue::Number(n))
+    }
+
+    fn visit_int(&mut self, i: i64) -> Option<JsonValue> {
+        Some(JsonValue::Int(i))
+    }
+
+    fn visit_raw_number(&mut self, raw: String) -> Option<JsonValue> {
+        Some(JsonValue::RawNumber(raw))
+    }
+
+    fn visit_string(&mut self, s: String) -> Option<JsonValue> {
+        Some(JsonValue::String(s))
+    }
+
+    fn visit_raw(&mut self, raw: String) -> Option<JsonValue> {
+        Some(JsonValue::RawJson(raw))
+    }
+
+    fn visit_array(&mut self, arr: Vec<JsonValue>) -> Option<JsonValue> {
+        Some(JsonValue::Array(arr))
+    }
+
+    fn visit_object(&mut self, map: Map) -> Option<JsonValue> {
+        Some(JsonValue::Obj(map))
+    }
+}
+
+impl JsonValue {
+    /// Runs `visitor` over the whole tree. A pruned root becomes `Null`.
+    pub fn accept(self, visitor: &mut impl Visitor) -> JsonValue {
+        self.walk(visitor).unwrap_or_default()
+    }
+
+    /// Replaces the value of every entry named in `keys`, at any depth.
+    pub fn redact(&mut self, keys: &[&str], replacement: JsonValue) {
+        let mut visitor = Redact { keys, replacement };
+        *self = std::mem::take(self).accept(&mut visitor);
+    }
+
+    fn walk(self, visitor: &mut impl Visitor) -> Option<JsonValue> {
+        match self {
+            JsonValue::Null => visitor.visit_null(),
+            JsonValue::Bool(b) => visitor.visit_bool(b),
+            JsonValue::Number(n) => visitor.visit_number(n),
+            JsonValue::Int(i) => visitor.visit_int(i),
+            JsonValue::RawNumber(raw) => visitor.visit_raw_number(raw),
+            JsonValue::String(s) => visitor.visit_string(s),
+            JsonValue::RawJson(raw) => visitor.visit_raw(raw),
+            JsonValue::Array(arr) => {
+                let arr = arr.into_iter().filter_map(|v| v.walk(visitor)).collect();
+                visitor.visit_array(arr)
+            }
+            JsonValue::Obj(map) => {
+                let map = map
+                    .into_iter()
+                    .filter_map(|(k, v)| Some((k, v.walk(visitor)?)))
+                    .collect();
+                visitor.visit_object(map)
+            }
+        }
+    }
+}
+
+struct Redact<'a> {
+    keys: &'a [&'a str],
+    replacement: JsonValue,
+}
+
+impl Visitor for Redact<'_> {
+    fn visit_object(&mut self, mut map: Map) -> Option<JsonValue> {
+        for (key, value) in map.iter_mut() {
+            if self.keys.contains(&key.as_str()) {
+                *value = self.replacement.clone();
+            }
+        }
+        Some(JsonValue::Obj(map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    struct Uppercase;
+
+    impl Visitor for Uppercase {
+        fn visit_string(&mut self, s: String) -> Option<JsonValue> {
+            Some(JsonValue::String(s.to_uppercase()))
+        }
+    }
+
+    struct DropNulls;
+
+    impl Visitor for DropNulls {
+        fn visit_null(&mut self) -> Option<JsonValue> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_uppercase_visitor() {
+        let value = from_str(r#"{"a": "one", "b": [1, "two", {"c": ["three"]}], "d": null}"#)
+            .unwrap()
+            .accept(&mut Uppercase);
+        assert_eq!(
+            value,
+            from_str(r#"{"a": "ONE", "b": [1, "TWO", {"c": ["THREE"]}], "d": null}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_redact() {
+        let mut value = from_str(
+            r#"{"user": {"name": "rex", "password": "hunter2", "keys": [{"password": 1}]}, "id": 7}"#,
+        )
+        .unwrap();
+        value.redact(&["password"], JsonValue::String("***".to_string()));
+        assert_eq!(
+            value,
+            from_str(
+                r#"{"user": {"name": "rex", "password": "***", "keys": [{"password": "***"}]}, "id": 7}"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pruning_visitor() {
+        let value = from_str(r#"{"a": null, "b": [1, null, 2]}"#)
+            .unwrap()
+            .accept(&mut DropNulls);
+        assert_eq!(value, from_str(r#"{"b": [1, 2]}"#).unwrap());
+        assert_eq!(JsonValue::Null.accept(&mut DropNulls), JsonValue::Null);
+    }
+}
@@ -0,0 +1,161 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_json_parser"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut pipe = child.stdin.take().unwrap();
+    let stdin = stdin.to_string();
+    // The binary may stop reading early, e.g. at the first error
+    let writer = std::thread::spawn(move || {
+        let _ = pipe.write_all(stdin.as_bytes());
+    });
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    output
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn pretty_prints_stdin() {
+    let output = run(&[], r#"{"a": [1, 2]}"#);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "{\n  \"a\": [\n    1,\n    2\n  ]\n}\n");
+}
+
+#[test]
+fn pretty_prints_file() {
+    let path = std::env::temp_dir().join(format!("json_cli_{}.json", std::process::id()));
+    std::fs::write(&path, "[true]").unwrap();
+    let output = run(&[path.to_str().unwrap()], "");
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "[\n  true\n]\n");
+}
+
+#[test]
+fn malformed_file() {
+    let path = std::env::temp_dir().join(format!("json_cli_bad_{}.json", std::process::id()));
+    std::fs::write(&path, "{\n  \"a\": [1 2]\n}").unwrap();
+    let file = path.to_str().unwrap();
+    let output = run(&[file], "");
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!(
+            "error: {}: expected ']' but found '2' at position 12 (line 2, column 11)\n",
+            file
+        )
+    );
+}
+
+#[test]
+fn missing_file() {
+    let path = std::env::temp_dir().join(format!("json_cli_missing_{}.json", std::process::id()));
+    let file = path.to_str().unwrap();
+    let output = run(&[file], "");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.starts_with(&format!("error: {}: ", file)),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn minify() {
+    let output = run(&["--minify"], "{ \"a\" : [ 1 , null ] }");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "{\"a\":[1,null]}\n");
+}
+
+#[test]
+fn ascii() {
+    let output = run(&["--minify", "--ascii"], r#"["é", "😀"]"#);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "[\"\\u00e9\",\"\\ud83d\\ude00\"]\n");
+}
+
+#[test]
+fn validate() {
+    let output = run(&["--validate"], "[1, 2]");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "valid\n");
+
+    let output = run(&["--validate"], "[1,\n 2");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 2"));
+}
+
+#[test]
+fn validate_large_stdin() {
+    let item = r#"{"id": 12345, "name": "some name", "tags": ["a", "b", "c"], "ok": true}"#;
+    let input = format!("[{}]", vec![item; 200_000].join(",\n"));
+    assert!(input.len() > 10_000_000);
+
+    let output = run(&["--validate"], &input);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "valid\n");
+
+    let mut items = vec![item.to_string(); 200_000];
+    items[149_999] = item.replace("true", "tru");
+    let broken = format!("[{}]", items.join(",\n"));
+    let output = run(&["--validate"], &broken);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("line 150000, column 67"), "{}", stderr);
+}
+
+#[test]
+fn query() {
+    let output = run(&["--query", "/a/1"], r#"{"a": [1, {"b": 2}]}"#);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "{\n  \"b\": 2\n}\n");
+}
+
+#[test]
+fn query_nested_file() {
+    let path = std::env::temp_dir().join(format!("json_cli_query_{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{"users": [{"name": "ann", "tags": ["a", "b"]}, {"name": "bob"}]}"#,
+    )
+    .unwrap();
+    let file = path.to_str().unwrap();
+    let name = run(&[file, "--query", "/users/1/name"], "");
+    let tag = run(&["--query", "/users/0/tags/1", file], "");
+    let missing = run(&[file, "--query", "/users/2/name"], "");
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(name.status.success());
+    assert_eq!(stdout(&name), "\"bob\"\n");
+    assert_eq!(stdout(&tag), "\"b\"\n");
+
+    assert_eq!(missing.status.code(), Some(1));
+    assert_eq!(stdout(&missing), "");
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("/users/2/name"));
+}
+
+#[test]
+fn bad_arguments() {
+    assert_eq!(run(&["--query"], "1").status.code(), Some(2));
+    assert_eq!(run(&["--query", "users"], "1").status.code(), Some(2));
+    assert_eq!(run(&["--minify", "--validate"], "1").status.code(), Some(2));
+    assert_eq!(run(&["--bogus"], "1").status.code(), Some(2));
+}